@@ -0,0 +1,186 @@
+//! `--alloc-traces` — instances and arrays carry the serial number of the
+//! stack trace that allocated them when the dump was taken with allocation
+//! tracking, e.g. by the legacy HPROF agent with `heap=sites`. The histogram
+//! is broken down by allocating stack trace, and `--alloc-collapsed` exports
+//! it as collapsed stacks for the flamegraph tooling.
+
+use std::fmt::Write;
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::collapsed;
+use crate::rendered_result::ClassAllocationStats;
+use crate::utils::{pluralize, pretty_bytes_size};
+
+#[derive(Serialize, JsonSchema, Clone)]
+pub struct AllocationTrace {
+    pub serial_number: u32,
+    // rendered stack frames, the allocating one first; empty when the dumper
+    // did not track allocations
+    pub frames: Vec<String>,
+    pub memory_usage: Vec<ClassAllocationStats>,
+}
+
+impl AllocationTrace {
+    fn total_size_bytes(&self) -> u64 {
+        self.memory_usage
+            .iter()
+            .map(|stats| stats.allocation_size_bytes)
+            .sum()
+    }
+
+    fn objects(&self) -> u64 {
+        self.memory_usage
+            .iter()
+            .map(|stats| stats.instance_count)
+            .sum()
+    }
+}
+
+#[derive(Serialize, JsonSchema, Clone)]
+pub struct AllocationTraceReport {
+    pub traces: Vec<AllocationTrace>,
+}
+
+impl AllocationTraceReport {
+    // Largest traces first, each histogram by decreasing size.
+    pub fn new(mut traces: Vec<AllocationTrace>) -> Self {
+        for trace in &mut traces {
+            trace
+                .memory_usage
+                .sort_by_key(|stats| std::cmp::Reverse(stats.allocation_size_bytes));
+        }
+        traces.sort_by_cached_key(|trace| {
+            (
+                std::cmp::Reverse(trace.total_size_bytes()),
+                trace.serial_number,
+            )
+        });
+        Self { traces }
+    }
+
+    pub fn render(&self, top: usize) -> String {
+        let mut out = String::new();
+        let traced = self.traces.iter().filter(|t| !t.frames.is_empty()).count();
+        let _ = writeln!(
+            out,
+            "\nFound {}, {traced} of them with frames:",
+            pluralize(
+                self.traces.len(),
+                "allocation stack trace",
+                "allocation stack traces"
+            )
+        );
+        if traced == 0 {
+            let _ = writeln!(
+                out,
+                "  the dump was probably taken without allocation tracking"
+            );
+            return out;
+        }
+        for trace in self.traces.iter().take(top) {
+            let _ = writeln!(
+                out,
+                "\n{} in {} allocated by stack trace {}:",
+                pretty_bytes_size(trace.total_size_bytes()),
+                pluralize(trace.objects(), "object", "objects"),
+                trace.serial_number
+            );
+            if trace.frames.is_empty() {
+                let _ = writeln!(out, "  <no stack frame>");
+            }
+            for frame in &trace.frames {
+                let _ = writeln!(out, "  at {frame}");
+            }
+            for stats in trace.memory_usage.iter().take(top) {
+                let _ = writeln!(
+                    out,
+                    "    {:>12} {:>10}  {}",
+                    pretty_bytes_size(stats.allocation_size_bytes),
+                    stats.instance_count,
+                    stats.class_name
+                );
+            }
+        }
+        if self.traces.len() > top {
+            let _ = writeln!(
+                out,
+                "\n... {}",
+                pluralize(
+                    self.traces.len() - top,
+                    "more stack trace",
+                    "more stack traces"
+                )
+            );
+        }
+        out
+    }
+
+    // Root frame first and the allocated class as leaf, valued in shallow
+    // bytes.
+    pub fn render_collapsed(&self) -> String {
+        collapsed::render(self.traces.iter().flat_map(|trace| {
+            trace.memory_usage.iter().map(|stats| {
+                let mut frames: Vec<String> = trace.frames.iter().rev().cloned().collect();
+                frames.push(stats.class_name.clone());
+                (frames, stats.allocation_size_bytes)
+            })
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> AllocationTraceReport {
+        AllocationTraceReport::new(vec![
+            AllocationTrace {
+                serial_number: 1,
+                frames: vec![],
+                memory_usage: vec![ClassAllocationStats::new("Thing".to_string(), 1, 16, 16)],
+            },
+            AllocationTrace {
+                serial_number: 2,
+                frames: vec!["Codec.decode".to_string(), "Main.main".to_string()],
+                memory_usage: vec![
+                    ClassAllocationStats::new("char[]".to_string(), 1, 24, 24),
+                    ClassAllocationStats::new("byte[]".to_string(), 2, 1024, 2048),
+                ],
+            },
+        ])
+    }
+
+    #[test]
+    fn largest_traces_are_rendered_first() {
+        let rendered = report().render(1);
+        assert!(rendered.starts_with(
+            "\nFound 2 allocation stack traces, 1 of them with frames:\n\n2.02KiB in 3 objects allocated by stack trace 2:\n  at Codec.decode\n  at Main.main\n         2.00KiB          2  byte[]\n"
+        ));
+        assert!(!rendered.contains("char[]"));
+        assert!(rendered.ends_with("\n... 1 more stack trace\n"));
+    }
+
+    #[test]
+    fn collapsed_stacks_end_with_the_allocated_class() {
+        assert_eq!(
+            report().render_collapsed(),
+            "Main.main;Codec.decode;byte[] 2048\nMain.main;Codec.decode;char[] 24\nThing 16\n"
+        );
+    }
+
+    #[test]
+    fn dumps_without_allocation_tracking_are_flagged() {
+        let report = AllocationTraceReport::new(vec![AllocationTrace {
+            serial_number: 1,
+            frames: vec![],
+            memory_usage: vec![],
+        }]);
+        assert!(
+            report
+                .render(5)
+                .contains("taken without allocation tracking")
+        );
+    }
+}
@@ -0,0 +1,267 @@
+//! `--android` — what usually matters in an Android dump: the bitmaps with
+//! their pixel memory, and the activities and fragments still in the heap
+//! after being destroyed, typically leaked through a static or a listener.
+//! Both are decoded from the instance fields of the framework classes.
+
+use std::fmt::Write;
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::utils::{pluralize, pretty_bytes_size};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AndroidClass {
+    Bitmap,
+    Activity,
+    Fragment,
+}
+
+impl AndroidClass {
+    // The framework class itself, subclasses are resolved by the caller.
+    pub fn from_class_name(class_name: &str) -> Option<Self> {
+        match class_name {
+            "android.graphics.Bitmap" => Some(Self::Bitmap),
+            "android.app.Activity" => Some(Self::Activity),
+            "android.app.Fragment"
+            | "androidx.fragment.app.Fragment"
+            | "android.support.v4.app.Fragment" => Some(Self::Fragment),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema, Debug, Clone)]
+pub struct Bitmap {
+    pub object_id: u64,
+    pub width: i32,
+    pub height: i32,
+    // shallow size of the `mBuffer` pixel array, `None` when the pixels live
+    // in native memory (Android 8+) or the array is not in the dump
+    pub buffer_bytes: Option<u64>,
+}
+
+#[derive(Serialize, JsonSchema, Debug, Clone)]
+pub struct AndroidComponent {
+    pub object_id: u64,
+    pub class_name: String,
+    // why the component should not be in the heap anymore, e.g. `destroyed`
+    pub leak_reasons: Vec<&'static str>,
+    // a fragment without a manager, never added or already destroyed: the
+    // fields do not tell both apart, it is listed but not counted as leaked
+    pub not_attached: bool,
+}
+
+impl AndroidComponent {
+    fn is_leaked(&self) -> bool {
+        !self.leak_reasons.is_empty()
+    }
+}
+
+#[derive(Serialize, JsonSchema, Clone)]
+pub struct AndroidReport {
+    pub bitmaps: Vec<Bitmap>,
+    pub activities: Vec<AndroidComponent>,
+    pub fragments: Vec<AndroidComponent>,
+}
+
+impl AndroidReport {
+    pub fn new(
+        mut bitmaps: Vec<Bitmap>,
+        mut activities: Vec<AndroidComponent>,
+        mut fragments: Vec<AndroidComponent>,
+    ) -> Self {
+        bitmaps.sort_unstable_by(|a, b| {
+            b.buffer_bytes
+                .cmp(&a.buffer_bytes)
+                .then(pixels(b).cmp(&pixels(a)))
+                .then(a.object_id.cmp(&b.object_id))
+        });
+        for components in [&mut activities, &mut fragments] {
+            components.sort_unstable_by(|a, b| {
+                b.is_leaked()
+                    .cmp(&a.is_leaked())
+                    .then_with(|| a.class_name.cmp(&b.class_name))
+                    .then(a.object_id.cmp(&b.object_id))
+            });
+        }
+        Self {
+            bitmaps,
+            activities,
+            fragments,
+        }
+    }
+
+    fn render_components(out: &mut String, kind: &str, components: &[AndroidComponent]) {
+        let singular = format!("{kind} instance");
+        let plural = format!("{kind} instances");
+        let leaked = components.iter().filter(|c| c.is_leaked()).count();
+        let _ = writeln!(
+            out,
+            "\nFound {}, {leaked} of them should have been collected:",
+            pluralize(components.len(), &singular, &plural)
+        );
+        for component in components.iter().filter(|c| c.is_leaked()) {
+            let _ = writeln!(
+                out,
+                "  <0x{:x}> {} ({})",
+                component.object_id,
+                component.class_name,
+                component.leak_reasons.join(", ")
+            );
+        }
+        let not_attached = components
+            .iter()
+            .filter(|c| c.not_attached && !c.is_leaked())
+            .count();
+        if not_attached > 0 {
+            let _ = writeln!(
+                out,
+                "{} not attached, never added or already destroyed:",
+                pluralize(
+                    not_attached,
+                    &format!("{singular} is"),
+                    &format!("{plural} are")
+                )
+            );
+            for component in components
+                .iter()
+                .filter(|c| c.not_attached && !c.is_leaked())
+            {
+                let _ = writeln!(
+                    out,
+                    "  <0x{:x}> {} (not attached)",
+                    component.object_id, component.class_name
+                );
+            }
+        }
+    }
+
+    pub fn render(&self, top: usize) -> String {
+        let mut out = String::new();
+        let buffers_bytes: u64 = self.bitmaps.iter().filter_map(|b| b.buffer_bytes).sum();
+        let _ = writeln!(
+            out,
+            "\nFound {} with {} of pixel buffers in the dump:",
+            pluralize(self.bitmaps.len(), "Android bitmap", "Android bitmaps"),
+            pretty_bytes_size(buffers_bytes)
+        );
+        if !self.bitmaps.is_empty() {
+            let _ = writeln!(out, "\n{:>12} {:>12}  Object", "Buffer", "Dimensions");
+        }
+        for bitmap in self.bitmaps.iter().take(top) {
+            let buffer = bitmap
+                .buffer_bytes
+                .map_or_else(|| "native".to_string(), pretty_bytes_size);
+            let dimensions = format!("{}x{}", bitmap.width, bitmap.height);
+            let _ = writeln!(
+                out,
+                "{buffer:>12} {dimensions:>12}  <0x{:x}>",
+                bitmap.object_id
+            );
+        }
+        if self.bitmaps.len() > top {
+            let remaining = self.bitmaps.len() - top;
+            let _ = writeln!(
+                out,
+                "  ... {}",
+                pluralize(remaining, "more bitmap", "more bitmaps")
+            );
+        }
+        Self::render_components(&mut out, "activity", &self.activities);
+        Self::render_components(&mut out, "fragment", &self.fragments);
+        out
+    }
+}
+
+fn pixels(bitmap: &Bitmap) -> i64 {
+    i64::from(bitmap.width) * i64::from(bitmap.height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn component(object_id: u64, leak_reasons: Vec<&'static str>) -> AndroidComponent {
+        AndroidComponent {
+            object_id,
+            class_name: "com.example.MainActivity".to_string(),
+            leak_reasons,
+            not_attached: false,
+        }
+    }
+
+    #[test]
+    fn framework_classes_are_recognized() {
+        assert_eq!(
+            AndroidClass::from_class_name("android.graphics.Bitmap"),
+            Some(AndroidClass::Bitmap)
+        );
+        assert_eq!(
+            AndroidClass::from_class_name("androidx.fragment.app.Fragment"),
+            Some(AndroidClass::Fragment)
+        );
+        assert_eq!(
+            AndroidClass::from_class_name("com.example.MainActivity"),
+            None
+        );
+    }
+
+    #[test]
+    fn largest_bitmaps_and_leaked_components_are_reported() {
+        let bitmap = |object_id, width, buffer_bytes| Bitmap {
+            object_id,
+            width,
+            height: 10,
+            buffer_bytes,
+        };
+        let report = AndroidReport::new(
+            vec![
+                bitmap(1, 10, None),
+                bitmap(2, 20, Some(816)),
+                bitmap(3, 5, Some(216)),
+            ],
+            vec![
+                component(4, vec![]),
+                component(5, vec!["destroyed", "finished"]),
+            ],
+            vec![],
+        );
+
+        assert_eq!(report.bitmaps[0].object_id, 2);
+        assert_eq!(report.activities[0].object_id, 5);
+
+        let rendered = report.render(2);
+        assert!(rendered.contains("Found 3 Android bitmaps with 1.01KiB of pixel buffers"));
+        assert!(rendered.contains(" 816.00bytes        20x10  <0x2>\n"));
+        assert!(rendered.contains("  ... 1 more bitmap\n"));
+        assert!(rendered.contains(
+            "Found 2 activity instances, 1 of them should have been collected:\n  <0x5> com.example.MainActivity (destroyed, finished)\n"
+        ));
+        assert!(rendered.contains("Found 0 fragment instances, 0 of them"));
+    }
+
+    #[test]
+    fn fragments_without_a_manager_are_not_counted_as_leaked() {
+        let not_attached = AndroidComponent {
+            not_attached: true,
+            ..component(2, vec![])
+        };
+        let report = AndroidReport::new(
+            vec![],
+            vec![],
+            vec![
+                component(1, vec!["removed"]),
+                not_attached,
+                component(3, vec![]),
+            ],
+        );
+
+        assert!(report.render(10).contains(concat!(
+            "Found 3 fragment instances, 1 of them should have been collected:\n",
+            "  <0x1> com.example.MainActivity (removed)\n",
+            "1 fragment instance is not attached, never added or already destroyed:\n",
+            "  <0x2> com.example.MainActivity (not attached)\n",
+        )));
+    }
+}
@@ -1,6 +1,9 @@
+use crate::diff_rules::{self, DiffRule};
 use crate::errors::HprofSlurpError;
 use crate::errors::HprofSlurpError::InputFileNotFound;
-use clap::{Arg, Command};
+use crate::table_export::TableColumns;
+use crate::trend::{self, TimeUnit};
+use clap::{Arg, ArgGroup, Command};
 use clap::{crate_authors, crate_description, crate_name, crate_version};
 use std::path::Path;
 
@@ -15,6 +18,22 @@ fn top_arg() -> Arg {
         .required(false)
 }
 
+fn json_arg() -> Arg {
+    Arg::new("json")
+        .help("additional JSON output in file")
+        .long("json")
+        .action(clap::ArgAction::SetTrue)
+}
+
+fn output_arg() -> Arg {
+    Arg::new("output")
+        .help("output file path for the JSON result (default: hprof-slurp-<timestamp>.json)")
+        .long("output")
+        .short('o')
+        .num_args(1)
+        .requires("json")
+}
+
 fn command() -> Command {
     Command::new(crate_name!())
         .version(crate_version!())
@@ -38,7 +57,77 @@ fn command() -> Command {
                         .num_args(1)
                         .required(true),
                 )
-                .arg(top_arg()),
+                .arg(top_arg())
+                .arg(
+                    Arg::new("csv")
+                        .help("write every class delta as CSV to this file")
+                        .long("csv")
+                        .value_name("FILE")
+                        .num_args(1),
+                )
+                .arg(json_arg())
+                .arg(output_arg())
+                .arg(
+                    Arg::new("max-net-growth")
+                        .help("fail with exit code 3 when the shallow heap grows by more than this size (e.g. 50MiB)")
+                        .long("max-net-growth")
+                        .value_name("SIZE")
+                        .num_args(1)
+                        .value_parser(diff_rules::parse_size),
+                )
+                .arg(
+                    Arg::new("max-class-growth")
+                        .help("fail with exit code 3 when a class present in both dumps grows by more than this percentage")
+                        .long("max-class-growth")
+                        .value_name("PERCENT")
+                        .num_args(1)
+                        .value_parser(clap::value_parser!(u32).range(1..)),
+                )
+                .arg(
+                    Arg::new("no-growth")
+                        .help("fail with exit code 3 when the instances of this class grow, can be repeated")
+                        .long("no-growth")
+                        .value_name("CLASS")
+                        .action(clap::ArgAction::Append),
+                ),
+        )
+        .subcommand(
+            Command::new("trend")
+                .about("rank the classes growing across several dumps of the same process")
+                .arg(
+                    Arg::new("files")
+                        .help("hprof files, ordered by their capture time")
+                        .value_name("FILE")
+                        .num_args(2..)
+                        .required(true),
+                )
+                .arg(top_arg())
+                .arg(
+                    Arg::new("per")
+                        .help("express the growth rates per second, minute, hour or day")
+                        .long("per")
+                        .value_name("UNIT")
+                        .num_args(1)
+                        .default_value("minute")
+                        .value_parser(trend::parse_time_unit),
+                )
+                .arg(
+                    Arg::new("csv")
+                        .help("write the series of every class as CSV to this file")
+                        .long("csv")
+                        .value_name("FILE")
+                        .num_args(1),
+                ),
+        )
+        .subcommand(
+            Command::new("schema")
+                .about("print the JSON Schema of the --json output")
+                .arg(
+                    Arg::new("diff")
+                        .help("print the schema of the diff --json output instead")
+                        .long("diff")
+                        .action(clap::ArgAction::SetTrue),
+                ),
         )
         .arg(
             Arg::new("file")
@@ -63,21 +152,168 @@ fn command() -> Command {
                 .action(clap::ArgAction::SetTrue),
         )
         .arg(
-            Arg::new("json")
-                .help("additional JSON output in file")
-                .long("json")
+            Arg::new("array-sizes")
+                .help("display the distribution of array sizes per array type")
+                .long("array-sizes")
                 .action(clap::ArgAction::SetTrue),
         )
         .arg(
-            Arg::new("output")
-                .help(
-                    "output file path for the JSON result (default: hprof-slurp-<timestamp>.json)",
-                )
-                .long("output")
-                .short('o')
+            Arg::new("zero-arrays")
+                .help("report primitive arrays whose elements are all zero")
+                .long("zero-arrays")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("mostly-zero")
+                .help("also report arrays with at least this percentage of zero elements")
+                .long("mostly-zero")
+                .value_name("PERCENT")
                 .num_args(1)
-                .requires("json"),
+                .value_parser(clap::value_parser!(u8).range(1..100))
+                .requires("zero-arrays"),
+        )
+        .arg(
+            Arg::new("heaps")
+                .help("display the classes per heap of the dump (Android app, zygote and image heaps)")
+                .long("heaps")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("exclude-shared-heaps")
+                .help("leave the Android zygote and image heaps out of the totals")
+                .long("exclude-shared-heaps")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("dumps")
+                .help("display the classes of each heap dump when the file contains several")
+                .long("dumps")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("diff-dumps")
+                .help("compare each heap dump of the file to the previous one")
+                .long("diff-dumps")
+                .action(clap::ArgAction::SetTrue)
+                .requires("dumps"),
+        )
+        .arg(
+            Arg::new("android")
+                .help("display Android bitmaps and the activities and fragments destroyed but still in the heap (indexes every heap object, 25 to 50 bytes each)")
+                .long("android")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("alloc-traces")
+                .help("break the histogram down by allocating stack trace (requires allocation tracking)")
+                .long("alloc-traces")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("alloc-collapsed")
+                .help("write the histogram by allocating stack trace as collapsed stacks to this file")
+                .long("alloc-collapsed")
+                .value_name("FILE")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("gc-roots")
+                .help("display GC roots per type with the classes of the objects they reference (indexes every heap object, 25 to 50 bytes each)")
+                .long("gc-roots")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("locals")
+                .help("list the objects held as locals by each rendered stack frame (indexes every heap object, 25 to 50 bytes each)")
+                .long("locals")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("locks")
+                .help("display the monitors with the threads referencing and waiting on them (indexes every heap object, 25 to 50 bytes each)")
+                .long("locks")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("group-threads")
+                .help("group threads sharing the same stack trace")
+                .long("group-threads")
+                .action(clap::ArgAction::SetTrue)
+                // the locals differ per thread, a group has one set of frames
+                .conflicts_with("locals"),
+        )
+        .arg(
+            Arg::new("thread-lifecycle")
+                .help("list the started and ended threads, warn about ended ones still in the heap")
+                .long("thread-lifecycle")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("jstack")
+                .help("write the threads in the jstack thread dump format to this file (indexes every heap object, 25 to 50 bytes each)")
+                .long("jstack")
+                .value_name("FILE")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("flamegraph")
+                .help("write the classes as an SVG flamegraph of their packages to this file")
+                .long("flamegraph")
+                .value_name("FILE")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("collapsed")
+                .help("write the classes as collapsed stacks of their packages to this file")
+                .long("collapsed")
+                .value_name("FILE")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("html")
+                .help("write the report as a self-contained HTML file to this file")
+                .long("html")
+                .value_name("FILE")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("markdown")
+                .help("write the report as Markdown to this file")
+                .long("markdown")
+                .value_name("FILE")
+                .num_args(1),
         )
+        .arg(
+            Arg::new("csv")
+                .help("write every class as CSV to this file")
+                .long("csv")
+                .value_name("FILE")
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("tsv")
+                .help("write every class as TSV to this file")
+                .long("tsv")
+                .value_name("FILE")
+                .num_args(1),
+        )
+        .group(ArgGroup::new("table-export").args(["csv", "tsv"]).multiple(true))
+        .arg(
+            Arg::new("package-column")
+                .help("add the package of each class to the CSV and TSV files")
+                .long("package-column")
+                .action(clap::ArgAction::SetTrue)
+                .requires("table-export"),
+        )
+        .arg(
+            Arg::new("classloader-column")
+                .help("add the class loader of each class to the CSV and TSV files")
+                .long("classloader-column")
+                .action(clap::ArgAction::SetTrue)
+                .requires("table-export"),
+        )
+        .arg(json_arg())
+        .arg(output_arg())
 }
 
 fn existing_file(raw_path: &str) -> Result<String, HprofSlurpError> {
@@ -102,49 +338,183 @@ pub fn get_args() -> Result<ParsedArgs, HprofSlurpError> {
         let from = existing_file(sub_matches.get_one::<String>("from").expect("impossible"))?;
         let to = existing_file(sub_matches.get_one::<String>("to").expect("impossible"))?;
         let top = get_top(sub_matches);
-        return Ok(ParsedArgs::Diff(DiffArgs { from, to, top }));
+        let csv_output = sub_matches.get_one::<String>("csv").cloned();
+        let json_output = sub_matches.get_flag("json");
+        let output_file = sub_matches.get_one::<String>("output").cloned();
+        let mut rules = Vec::new();
+        if let Some(bytes) = sub_matches.get_one::<u64>("max-net-growth") {
+            rules.push(DiffRule::MaxNetGrowth(*bytes));
+        }
+        if let Some(percent) = sub_matches.get_one::<u32>("max-class-growth") {
+            rules.push(DiffRule::MaxClassGrowthPercent(*percent));
+        }
+        for class_name in sub_matches
+            .get_many::<String>("no-growth")
+            .into_iter()
+            .flatten()
+        {
+            rules.push(DiffRule::NoInstanceGrowth(class_name.clone()));
+        }
+        return Ok(ParsedArgs::Diff(DiffArgs {
+            from,
+            to,
+            top,
+            csv_output,
+            json_output,
+            output_file,
+            rules,
+        }));
+    }
+    if let Some(("trend", sub_matches)) = matches.subcommand() {
+        let files = sub_matches
+            .get_many::<String>("files")
+            .expect("impossible")
+            .map(|file| existing_file(file))
+            .collect::<Result<_, _>>()?;
+        let top = get_top(sub_matches);
+        let time_unit = *sub_matches.get_one::<TimeUnit>("per").expect("impossible");
+        let csv_output = sub_matches.get_one::<String>("csv").cloned();
+        return Ok(ParsedArgs::Trend(TrendArgs {
+            files,
+            top,
+            time_unit,
+            csv_output,
+        }));
+    }
+    if let Some(("schema", sub_matches)) = matches.subcommand() {
+        return Ok(ParsedArgs::Schema {
+            diff: sub_matches.get_flag("diff"),
+        });
     }
 
     let file_path = existing_file(matches.get_one::<String>("file").expect("impossible"))?;
     let top = get_top(&matches);
     let debug = matches.get_flag("debug");
-    let list_strings = matches.get_flag("list-strings");
+    let jstack_output = matches.get_one::<String>("jstack").cloned();
+    let alloc_collapsed_output = matches.get_one::<String>("alloc-collapsed").cloned();
+    let flamegraph_output = matches.get_one::<String>("flamegraph").cloned();
+    let collapsed_output = matches.get_one::<String>("collapsed").cloned();
+    let html_output = matches.get_one::<String>("html").cloned();
+    let markdown_output = matches.get_one::<String>("markdown").cloned();
+    let csv_output = matches.get_one::<String>("csv").cloned();
+    let tsv_output = matches.get_one::<String>("tsv").cloned();
+    let table_columns = TableColumns {
+        package: matches.get_flag("package-column"),
+        class_loader: matches.get_flag("classloader-column"),
+    };
+    let reports = ReportOptions {
+        list_strings: matches.get_flag("list-strings"),
+        array_sizes: matches.get_flag("array-sizes"),
+        zero_arrays: matches.get_flag("zero-arrays"),
+        mostly_zero_percent: matches.get_one::<u8>("mostly-zero").copied(),
+        heaps: matches.get_flag("heaps"),
+        exclude_shared_heaps: matches.get_flag("exclude-shared-heaps"),
+        dumps: matches.get_flag("dumps"),
+        diff_dumps: matches.get_flag("diff-dumps"),
+        android: matches.get_flag("android"),
+        alloc_traces: matches.get_flag("alloc-traces"),
+        alloc_collapsed: alloc_collapsed_output.is_some(),
+        gc_roots: matches.get_flag("gc-roots"),
+        locals: matches.get_flag("locals"),
+        locks: matches.get_flag("locks"),
+        group_threads: matches.get_flag("group-threads"),
+        thread_lifecycle: matches.get_flag("thread-lifecycle"),
+        class_loaders: table_columns.class_loader,
+        jstack: jstack_output.is_some(),
+    };
     let json_output = matches.get_flag("json");
     let output_file = matches.get_one::<String>("output").cloned();
     let args = Args {
         file_path,
         top,
         debug,
-        list_strings,
+        reports,
+        jstack_output,
+        alloc_collapsed_output,
+        flamegraph_output,
+        collapsed_output,
+        html_output,
+        markdown_output,
+        csv_output,
+        tsv_output,
+        table_columns,
         json_output,
         output_file,
     };
-    Ok(ParsedArgs::Analyze(args))
+    Ok(ParsedArgs::Analyze(Box::new(args)))
 }
 
 pub enum ParsedArgs {
-    Analyze(Args),
+    Analyze(Box<Args>),
     Diff(DiffArgs),
+    Trend(TrendArgs),
+    Schema { diff: bool },
 }
 
 pub struct Args {
     pub file_path: String,
     pub top: usize,
     pub debug: bool,
-    pub list_strings: bool,
+    pub reports: ReportOptions,
+    pub jstack_output: Option<String>,
+    pub alloc_collapsed_output: Option<String>,
+    pub flamegraph_output: Option<String>,
+    pub collapsed_output: Option<String>,
+    pub html_output: Option<String>,
+    pub markdown_output: Option<String>,
+    pub csv_output: Option<String>,
+    pub tsv_output: Option<String>,
+    pub table_columns: TableColumns,
     pub json_output: bool,
     pub output_file: Option<String>,
 }
 
+// Optional report sections, all disabled by default.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ReportOptions {
+    pub list_strings: bool,
+    pub array_sizes: bool,
+    pub zero_arrays: bool,
+    pub mostly_zero_percent: Option<u8>,
+    pub heaps: bool,
+    pub exclude_shared_heaps: bool,
+    pub dumps: bool,
+    pub diff_dumps: bool,
+    pub android: bool,
+    pub alloc_traces: bool,
+    pub alloc_collapsed: bool,
+    pub gc_roots: bool,
+    pub locals: bool,
+    pub locks: bool,
+    pub group_threads: bool,
+    pub thread_lifecycle: bool,
+    pub class_loaders: bool,
+    pub jstack: bool,
+}
+
 pub struct DiffArgs {
     pub from: String,
     pub to: String,
     pub top: usize,
+    pub csv_output: Option<String>,
+    pub json_output: bool,
+    pub output_file: Option<String>,
+    // empty unless gating flags are given
+    pub rules: Vec<DiffRule>,
+}
+
+pub struct TrendArgs {
+    pub files: Vec<String>,
+    pub top: usize,
+    // unit of the growth rates, `--per`
+    pub time_unit: TimeUnit,
+    pub csv_output: Option<String>,
 }
 
 #[cfg(test)]
 mod args_tests {
     use crate::args::command;
+    use crate::errors::HprofSlurpError;
 
     #[test]
     fn verify_command() {
@@ -166,6 +536,15 @@ mod args_tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn schema_subcommand_needs_no_file() {
+        let result = command().try_get_matches_from(["hprof-slurp", "schema"]);
+        assert!(result.is_ok());
+
+        let result = command().try_get_matches_from(["hprof-slurp", "schema", "f.hprof"]);
+        assert!(result.is_err());
+    }
+
     #[test]
     fn diff_subcommand_requires_two_files() {
         let result = command().try_get_matches_from(["hprof-slurp", "diff", "a.hprof", "b.hprof"]);
@@ -183,6 +562,163 @@ mod args_tests {
             "5",
         ]);
         assert!(result.is_ok(), "diff should accept --top");
+
+        let result = command().try_get_matches_from([
+            "hprof-slurp",
+            "diff",
+            "a.hprof",
+            "b.hprof",
+            "--csv",
+            "diff.csv",
+            "--json",
+            "-o",
+            "diff.json",
+        ]);
+        assert!(result.is_ok(), "diff should accept --csv and --json");
+
+        let result = command().try_get_matches_from([
+            "hprof-slurp",
+            "diff",
+            "a.hprof",
+            "b.hprof",
+            "-o",
+            "diff.json",
+        ]);
+        assert!(result.is_err(), "diff --output should require --json");
+    }
+
+    #[test]
+    fn trend_subcommand_requires_two_files() {
+        let result = command().try_get_matches_from([
+            "hprof-slurp",
+            "trend",
+            "a.hprof",
+            "b.hprof",
+            "c.hprof",
+            "--csv",
+            "trend.csv",
+        ]);
+        assert!(result.is_ok());
+
+        let result = command().try_get_matches_from(["hprof-slurp", "trend", "a.hprof"]);
+        assert!(result.is_err(), "trend should require at least two files");
+
+        let result = command().try_get_matches_from([
+            "hprof-slurp",
+            "trend",
+            "a.hprof",
+            "b.hprof",
+            "--per",
+            "week",
+        ]);
+        assert!(result.is_err(), "unknown time units should be rejected");
+    }
+
+    #[test]
+    fn diff_rules_are_parsed() {
+        let matches = command()
+            .try_get_matches_from([
+                "hprof-slurp",
+                "diff",
+                "a.hprof",
+                "b.hprof",
+                "--max-net-growth",
+                "50MiB",
+                "--no-growth",
+                "com.acme.Session",
+                "--no-growth",
+                "com.acme.Cart",
+            ])
+            .unwrap();
+        let (_, sub_matches) = matches.subcommand().unwrap();
+        assert_eq!(
+            sub_matches.get_one::<u64>("max-net-growth"),
+            Some(&(50 * 1024 * 1024))
+        );
+        assert_eq!(
+            sub_matches.get_many::<String>("no-growth").unwrap().count(),
+            2
+        );
+
+        let result = command().try_get_matches_from([
+            "hprof-slurp",
+            "diff",
+            "a.hprof",
+            "b.hprof",
+            "--max-net-growth",
+            "50MB",
+        ]);
+        assert!(result.is_err(), "sizes should use binary units");
+
+        let result = command().try_get_matches_from([
+            "hprof-slurp",
+            "diff",
+            "a.hprof",
+            "b.hprof",
+            "--max-class-growth",
+            "0",
+        ]);
+        assert!(result.is_err(), "a zero percent growth should be rejected");
+    }
+
+    #[test]
+    fn violated_diff_rules_exit_apart_from_usage_errors() {
+        let usage_error = command()
+            .try_get_matches_from([
+                "hprof-slurp",
+                "diff",
+                "a.hprof",
+                "b.hprof",
+                "--max-net-growth",
+                "50MB",
+            ])
+            .unwrap_err();
+        let violated = HprofSlurpError::DiffRulesViolated { violated: 1 };
+
+        assert_eq!(usage_error.exit_code(), 2);
+        assert_eq!(violated.exit_code(), 3);
+        assert_eq!(HprofSlurpError::InvalidIdSize.exit_code(), 1);
+    }
+
+    #[test]
+    fn mostly_zero_requires_zero_arrays_and_a_percentage() {
+        let result =
+            command().try_get_matches_from(["hprof-slurp", "f.hprof", "--mostly-zero", "90"]);
+        assert!(result.is_err());
+
+        let result = command().try_get_matches_from([
+            "hprof-slurp",
+            "f.hprof",
+            "--zero-arrays",
+            "--mostly-zero",
+            "100",
+        ]);
+        assert!(result.is_err(), "100% is the all-zero report");
+
+        let result = command().try_get_matches_from([
+            "hprof-slurp",
+            "f.hprof",
+            "--zero-arrays",
+            "--mostly-zero",
+            "90",
+        ]);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn table_columns_require_a_table_export() {
+        let result = command().try_get_matches_from(["hprof-slurp", "f.hprof", "--package-column"]);
+        assert!(result.is_err());
+
+        let result = command().try_get_matches_from([
+            "hprof-slurp",
+            "f.hprof",
+            "--tsv",
+            "out.tsv",
+            "--package-column",
+            "--classloader-column",
+        ]);
+        assert!(result.is_ok());
     }
 
     #[test]
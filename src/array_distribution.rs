@@ -0,0 +1,266 @@
+//! `--array-sizes` — log-bucketed distribution of array lengths per array
+//! type, to tell "a million tiny arrays" apart from "three giant ones".
+
+use std::fmt::Write;
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::utils::{pluralize, pretty_bytes_size};
+
+// bucket 0 holds empty arrays, bucket `k` holds lengths in `[2^(k-1), 2^k)`
+const BUCKETS: usize = u32::BITS as usize + 1;
+
+#[derive(Debug, Copy, Clone)]
+pub struct LengthHistogram {
+    arrays: [u64; BUCKETS],
+    bytes: [u64; BUCKETS],
+    max_number_of_elements: u32,
+}
+
+impl LengthHistogram {
+    pub const fn empty() -> Self {
+        Self {
+            arrays: [0; BUCKETS],
+            bytes: [0; BUCKETS],
+            max_number_of_elements: 0,
+        }
+    }
+
+    pub fn add_array(&mut self, number_of_elements: u32, size_bytes: u64) {
+        let bucket = (u32::BITS - number_of_elements.leading_zeros()) as usize;
+        self.arrays[bucket] += 1;
+        self.bytes[bucket] += size_bytes;
+        self.max_number_of_elements = self.max_number_of_elements.max(number_of_elements);
+    }
+
+    pub fn merge(&mut self, other: &Self) {
+        for bucket in 0..BUCKETS {
+            self.arrays[bucket] += other.arrays[bucket];
+            self.bytes[bucket] += other.bytes[bucket];
+        }
+        self.max_number_of_elements = self
+            .max_number_of_elements
+            .max(other.max_number_of_elements);
+    }
+
+    // Inclusive range of lengths of a bucket, capped by the largest length seen.
+    fn bucket_range(&self, bucket: usize) -> (u32, u32) {
+        let (min, max) = match bucket {
+            0 => (0, 0),
+            b => (1 << (b - 1), u32::MAX >> (BUCKETS - 1 - b)),
+        };
+        (min, max.min(self.max_number_of_elements))
+    }
+
+    // Upper bound of the bucket holding the given percentile of arrays.
+    fn percentile(&self, percent: u64) -> u32 {
+        let total: u64 = self.arrays.iter().sum();
+        let rank = (total * percent).div_ceil(100).max(1);
+        let mut cumulated = 0;
+        for (bucket, arrays) in self.arrays.iter().enumerate() {
+            cumulated += arrays;
+            if cumulated >= rank {
+                return self.bucket_range(bucket).1;
+            }
+        }
+        self.max_number_of_elements
+    }
+
+    // `size_of` converts a length into the shallow size of one such array.
+    pub fn distribution<F>(&self, array_type: String, size_of: F) -> ArrayDistribution
+    where
+        F: Fn(u32) -> u64,
+    {
+        let percentile = |percent| {
+            let max_elements = self.percentile(percent);
+            ArrayPercentile {
+                max_elements,
+                max_size_bytes: size_of(max_elements),
+            }
+        };
+        let buckets = (0..BUCKETS)
+            .filter(|bucket| self.arrays[*bucket] > 0)
+            .map(|bucket| {
+                let (min_elements, max_elements) = self.bucket_range(bucket);
+                ArrayBucket {
+                    min_elements,
+                    max_elements,
+                    arrays: self.arrays[bucket],
+                    total_size_bytes: self.bytes[bucket],
+                }
+            })
+            .collect();
+        ArrayDistribution {
+            array_type,
+            arrays: self.arrays.iter().sum(),
+            total_size_bytes: self.bytes.iter().sum(),
+            p50: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+            max: percentile(100),
+            buckets,
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema, Clone, Copy)]
+pub struct ArrayPercentile {
+    pub max_elements: u32,
+    pub max_size_bytes: u64,
+}
+
+#[derive(Serialize, JsonSchema, Clone)]
+pub struct ArrayBucket {
+    pub min_elements: u32,
+    pub max_elements: u32,
+    pub arrays: u64,
+    pub total_size_bytes: u64,
+}
+
+#[derive(Serialize, JsonSchema, Clone)]
+pub struct ArrayDistribution {
+    pub array_type: String,
+    pub arrays: u64,
+    pub total_size_bytes: u64,
+    pub p50: ArrayPercentile,
+    pub p90: ArrayPercentile,
+    pub p99: ArrayPercentile,
+    pub max: ArrayPercentile,
+    pub buckets: Vec<ArrayBucket>,
+}
+
+impl ArrayDistribution {
+    // Renders the `top` array types by total size, `distributions` is expected
+    // to be sorted accordingly.
+    pub fn render(distributions: &[Self], top: usize) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "\nArray size distribution (top {} of {} array types by total size):",
+            top.min(distributions.len()),
+            distributions.len()
+        );
+        for distribution in distributions.iter().take(top) {
+            let pretty = |p: &ArrayPercentile| {
+                format!(
+                    "{} ({})",
+                    p.max_elements,
+                    pretty_bytes_size(p.max_size_bytes)
+                )
+            };
+            let _ = writeln!(
+                out,
+                "\n{}: {}, {}",
+                distribution.array_type,
+                pluralize(distribution.arrays, "array", "arrays"),
+                pretty_bytes_size(distribution.total_size_bytes)
+            );
+            let _ = writeln!(
+                out,
+                "  p50 <= {}, p90 <= {}, p99 <= {}, max {}",
+                pretty(&distribution.p50),
+                pretty(&distribution.p90),
+                pretty(&distribution.p99),
+                pretty(&distribution.max)
+            );
+            let _ = writeln!(
+                out,
+                "{:>25} {:>10} {:>12}",
+                "elements", "arrays", "total size"
+            );
+            for bucket in &distribution.buckets {
+                let elements = if bucket.min_elements == bucket.max_elements {
+                    bucket.min_elements.to_string()
+                } else {
+                    format!("{}..{}", bucket.min_elements, bucket.max_elements)
+                };
+                let _ = writeln!(
+                    out,
+                    "{elements:>25} {:>10} {:>12}",
+                    bucket.arrays,
+                    pretty_bytes_size(bucket.total_size_bytes)
+                );
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lengths_are_bucketed_by_power_of_two() {
+        let mut histogram = LengthHistogram::empty();
+        for length in [0, 1, 2, 3, 4, 7, 8] {
+            histogram.add_array(length, u64::from(length));
+        }
+
+        let distribution = histogram.distribution("int[]".to_string(), u64::from);
+        let buckets: Vec<_> = distribution
+            .buckets
+            .iter()
+            .map(|b| (b.min_elements, b.max_elements, b.arrays, b.total_size_bytes))
+            .collect();
+
+        assert_eq!(
+            buckets,
+            vec![
+                (0, 0, 1, 0),
+                (1, 1, 1, 1),
+                (2, 3, 2, 5),
+                (4, 7, 2, 11),
+                (8, 8, 1, 8)
+            ]
+        );
+        assert_eq!(distribution.arrays, 7);
+        assert_eq!(distribution.total_size_bytes, 25);
+    }
+
+    #[test]
+    fn percentiles_separate_many_small_from_few_giant_arrays() {
+        let mut histogram = LengthHistogram::empty();
+        for _ in 0..98 {
+            histogram.add_array(10, 56);
+        }
+        histogram.add_array(1_000_000, 4_000_016);
+        histogram.add_array(2_000_000, 8_000_016);
+
+        let distribution = histogram.distribution("int[]".to_string(), |n| u64::from(n) * 4);
+
+        assert_eq!(distribution.p50.max_elements, 15);
+        assert_eq!(distribution.p90.max_elements, 15);
+        assert_eq!(distribution.p99.max_elements, 1_048_575);
+        assert_eq!(distribution.max.max_elements, 2_000_000);
+        assert_eq!(distribution.max.max_size_bytes, 8_000_000);
+    }
+
+    #[test]
+    fn largest_bucket_does_not_overflow() {
+        let mut histogram = LengthHistogram::empty();
+        histogram.add_array(u32::MAX, 1);
+
+        let distribution = histogram.distribution("byte[]".to_string(), u64::from);
+
+        assert_eq!(distribution.buckets[0].min_elements, 1 << 31);
+        assert_eq!(distribution.buckets[0].max_elements, u32::MAX);
+    }
+
+    #[test]
+    fn merged_histograms_add_up() {
+        let mut a = LengthHistogram::empty();
+        a.add_array(5, 10);
+        let mut b = LengthHistogram::empty();
+        b.add_array(6, 12);
+        b.add_array(100, 200);
+
+        a.merge(&b);
+        let distribution = a.distribution("long[]".to_string(), u64::from);
+
+        assert_eq!(distribution.arrays, 3);
+        assert_eq!(distribution.total_size_bytes, 222);
+        assert_eq!(distribution.max.max_elements, 100);
+    }
+}
@@ -0,0 +1,78 @@
+//! Collapsed stacks, the `frame;frame;frame value` text format read by
+//! `flamegraph.pl`, inferno and speedscope. One line per distinct stack, the
+//! root frame first. `--collapsed` writes the histogram in this format.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::descriptor::package_frames;
+use crate::rendered_result::ClassAllocationStats;
+
+// `;` separates the frames and a line ends with the value after its last space.
+fn sanitize(frame: &str) -> String {
+    frame.replace(';', ",").replace(['\n', '\r'], " ")
+}
+
+// Sums the values of identical stacks, stacks without value are dropped.
+pub fn render<I>(stacks: I) -> String
+where
+    I: IntoIterator<Item = (Vec<String>, u64)>,
+{
+    let mut value_by_stack: BTreeMap<String, u64> = BTreeMap::new();
+    for (frames, value) in stacks {
+        if value == 0 || frames.is_empty() {
+            continue;
+        }
+        let stack = frames
+            .iter()
+            .map(|frame| sanitize(frame))
+            .collect::<Vec<_>>()
+            .join(";");
+        *value_by_stack.entry(stack).or_default() += value;
+    }
+    let mut out = String::new();
+    for (stack, value) in value_by_stack {
+        let _ = writeln!(out, "{stack} {value}");
+    }
+    out
+}
+
+// The package segments then the class as frames, valued in shallow bytes.
+pub fn render_classes(memory_usage: &[ClassAllocationStats]) -> String {
+    render(memory_usage.iter().map(|stats| {
+        let frames = package_frames(&stats.class_name)
+            .into_iter()
+            .map(ToString::to_string)
+            .collect();
+        (frames, stats.allocation_size_bytes)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_stacks_are_summed_and_frames_sanitized() {
+        let frames = |names: &[&str]| names.iter().map(ToString::to_string).collect();
+        let rendered = render(vec![
+            (frames(&["main", "decode", "byte[]"]), 16),
+            (frames(&["main", "a;b"]), 8),
+            (frames(&["main", "decode", "byte[]"]), 32),
+            (frames(&["main", "idle"]), 0),
+        ]);
+        assert_eq!(rendered, "main;a,b 8\nmain;decode;byte[] 48\n");
+    }
+
+    #[test]
+    fn classes_are_collapsed_by_package() {
+        let memory_usage = vec![
+            ClassAllocationStats::new("java.lang.String".to_string(), 3, 24, 72),
+            ClassAllocationStats::new("byte[]".to_string(), 1, 96, 96),
+        ];
+        assert_eq!(
+            render_classes(&memory_usage),
+            "byte[] 96\njava;lang;String 72\n"
+        );
+    }
+}
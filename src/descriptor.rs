@@ -0,0 +1,187 @@
+//! JVM type descriptors (JVMS §4.3) decoded into Java source notation, e.g.
+//! `[[C` as `char[][]` and `(ILjava/lang/String;)V` as `(int, java.lang.String)`,
+//! and the packages of the decoded class names.
+
+fn primitive_name(descriptor: u8) -> Option<&'static str> {
+    let name = match descriptor {
+        b'Z' => "boolean",
+        b'B' => "byte",
+        b'C' => "char",
+        b'S' => "short",
+        b'I' => "int",
+        b'J' => "long",
+        b'F' => "float",
+        b'D' => "double",
+        _ => return None,
+    };
+    Some(name)
+}
+
+// Decodes the field descriptor at the start of `descriptor`, returns it with
+// the number of bytes consumed.
+fn decode_field_prefix(descriptor: &str) -> Option<(String, usize)> {
+    let dimensions = descriptor.bytes().take_while(|b| *b == b'[').count();
+    let element = &descriptor[dimensions..];
+    let (element_name, element_len) = match element.as_bytes().first()? {
+        b'L' => {
+            let end = element.find(';')?;
+            (element[1..end].replace('/', "."), end + 1)
+        }
+        b => (primitive_name(*b)?.to_string(), 1),
+    };
+    let decoded = format!("{element_name}{}", "[]".repeat(dimensions));
+    Some((decoded, dimensions + element_len))
+}
+
+// `None` if `descriptor` is not exactly one field descriptor.
+pub fn decode_field_descriptor(descriptor: &str) -> Option<String> {
+    match decode_field_prefix(descriptor)? {
+        (decoded, len) if len == descriptor.len() => Some(decoded),
+        _ => None,
+    }
+}
+
+// Class names are binary names (`java/lang/String`) except for array classes
+// which are named by their descriptor (`[[C`). Some dumpers (Android, the
+// legacy HPROF agent) already write arrays as `char[][]`, kept as is.
+pub fn decode_class_name(raw_class_name: &str) -> String {
+    if raw_class_name.starts_with('[')
+        && let Some(decoded) = decode_field_descriptor(raw_class_name)
+    {
+        return decoded;
+    }
+    raw_class_name.replace('/', ".")
+}
+
+// Parameter types of a method descriptor, `None` if malformed.
+pub fn decode_method_parameters(descriptor: &str) -> Option<Vec<String>> {
+    let mut rest = descriptor.strip_prefix('(')?;
+    let mut parameters = Vec::new();
+    while !rest.starts_with(')') {
+        let (parameter, len) = decode_field_prefix(rest)?;
+        parameters.push(parameter);
+        rest = &rest[len..];
+    }
+    // the return type must be the only thing left, `V` is only valid there
+    let return_type = &rest[1..];
+    if return_type != "V" {
+        decode_field_descriptor(return_type)?;
+    }
+    Some(parameters)
+}
+
+// Package of a decoded class name or of its array element class, `None` for
+// the primitive types, the default package and the placeholder names.
+pub fn split_package(class_name: &str) -> Option<(&str, &str)> {
+    if class_name.starts_with('<') {
+        return None;
+    }
+    // `[` cannot appear in a package, only in the array suffix
+    let element_end = class_name.find('[').unwrap_or(class_name.len());
+    let package_end = class_name[..element_end].rfind('.')?;
+    Some((&class_name[..package_end], &class_name[package_end + 1..]))
+}
+
+// Package of the class, empty if it has none.
+pub fn package_name(class_name: &str) -> &str {
+    split_package(class_name).map_or("", |(package, _)| package)
+}
+
+// Package segments then the class name, e.g. `java`, `util`, `HashMap$Node[]`.
+// Array and placeholder names without a package are a single frame.
+pub fn package_frames(class_name: &str) -> Vec<&str> {
+    match split_package(class_name) {
+        Some((package, simple_name)) => {
+            let mut frames: Vec<&str> = package.split('.').collect();
+            frames.push(simple_name);
+            frames
+        }
+        None => vec![class_name],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn field_descriptors() {
+        assert_eq!(decode_field_descriptor("I").as_deref(), Some("int"));
+        assert_eq!(decode_field_descriptor("[[C").as_deref(), Some("char[][]"));
+        assert_eq!(
+            decode_field_descriptor("[Ljava/lang/Object;").as_deref(),
+            Some("java.lang.Object[]")
+        );
+        assert_eq!(
+            decode_field_descriptor("[[Ljava/util/HashMap$Node;").as_deref(),
+            Some("java.util.HashMap$Node[][]")
+        );
+        assert_eq!(decode_field_descriptor("[Ljava/lang/Object"), None);
+        assert_eq!(decode_field_descriptor("[X"), None);
+        // `void` is only a return type
+        assert_eq!(decode_field_descriptor("V"), None);
+        assert_eq!(decode_field_descriptor("[V"), None);
+        assert_eq!(decode_field_descriptor("II"), None);
+        assert_eq!(decode_field_descriptor(""), None);
+    }
+
+    #[test]
+    fn class_names() {
+        assert_eq!(decode_class_name("java/lang/String"), "java.lang.String");
+        assert_eq!(decode_class_name("[[D"), "double[][]");
+        assert_eq!(
+            decode_class_name("[Ljava/lang/String;"),
+            "java.lang.String[]"
+        );
+        // already readable names are kept
+        assert_eq!(
+            decode_class_name("java.lang.String[]"),
+            "java.lang.String[]"
+        );
+        assert_eq!(decode_class_name("int[][]"), "int[][]");
+    }
+
+    #[test]
+    fn method_parameters() {
+        assert_eq!(decode_method_parameters("()V"), Some(vec![]));
+        assert_eq!(
+            decode_method_parameters("(J)V"),
+            Some(vec!["long".to_string()])
+        );
+        assert_eq!(
+            decode_method_parameters("(I[[BLjava/lang/String;)Ljava/lang/Object;"),
+            Some(vec![
+                "int".to_string(),
+                "byte[][]".to_string(),
+                "java.lang.String".to_string()
+            ])
+        );
+        assert_eq!(decode_method_parameters("(I"), None);
+        assert_eq!(decode_method_parameters("(I)"), None);
+        assert_eq!(decode_method_parameters("I)V"), None);
+        assert_eq!(decode_method_parameters("(V)V"), None);
+        assert_eq!(decode_method_parameters("()[V"), None);
+    }
+
+    #[test]
+    fn packages_are_taken_from_the_element_class() {
+        assert_eq!(package_name("java.util.HashMap$Node[]"), "java.util");
+        assert_eq!(package_name("byte[]"), "");
+        assert_eq!(package_name("Main"), "");
+        assert_eq!(package_name("<unknown class 0x1.2>"), "");
+    }
+
+    #[test]
+    fn class_names_are_split_on_packages() {
+        assert_eq!(
+            package_frames("java.util.HashMap$Node[]"),
+            vec!["java", "util", "HashMap$Node[]"]
+        );
+        assert_eq!(package_frames("byte[]"), vec!["byte[]"]);
+        assert_eq!(package_frames("Main"), vec!["Main"]);
+        assert_eq!(
+            package_frames("<unknown class 0x1.2>"),
+            vec!["<unknown class 0x1.2>"]
+        );
+    }
+}
@@ -1,38 +1,84 @@
 //! `hprof-slurp diff <FROM> <TO>` — per-class delta in instance count and
 //! shallow bytes between two snapshots of the same process. Classes whose
 //! footprint grew the most between two captures are the leak suspects a
-//! single static dump can't reveal.
+//! single static dump can't reveal. `--json` and `--csv` write every delta
+//! for tracking regressions automatically.
 
 use std::cmp::Reverse;
 use std::fmt::Write;
 
 use ahash::AHashMap;
+use schemars::JsonSchema;
+use serde::Serialize;
 
 use crate::rendered_result::ClassAllocationStats;
+use crate::table_export::TableFormat;
 use crate::utils::{pretty_bytes_size, pretty_signed_bytes_size};
 
+#[derive(Serialize, JsonSchema, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffChange {
+    // only in the `to` dump
+    Added,
+    // only in the `from` dump
+    Removed,
+    Changed,
+}
+
+impl DiffChange {
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Added => "added",
+            Self::Removed => "removed",
+            Self::Changed => "changed",
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema, Clone, Debug)]
 pub struct DiffEntry {
     pub class_name: String,
+    pub change: DiffChange,
     pub instances_from: u64,
     pub instances_to: u64,
+    pub delta_instances: i64,
     pub bytes_from: u64,
     pub bytes_to: u64,
+    pub delta_bytes: i64,
 }
 
-impl DiffEntry {
-    fn delta_bytes(&self) -> i64 {
-        self.bytes_to as i64 - self.bytes_from as i64
-    }
+// Shallow totals of both dumps over all classes.
+#[derive(Serialize, JsonSchema, Clone, Copy, Debug)]
+pub struct DiffTotals {
+    pub instances_from: u64,
+    pub instances_to: u64,
+    pub delta_instances: i64,
+    pub bytes_from: u64,
+    pub bytes_to: u64,
+    pub delta_bytes: i64,
+}
 
-    fn delta_instances(&self) -> i64 {
-        self.instances_to as i64 - self.instances_from as i64
+impl DiffTotals {
+    pub fn new(from: &[ClassAllocationStats], to: &[ClassAllocationStats]) -> Self {
+        let instances_from: u64 = from.iter().map(|s| s.instance_count).sum();
+        let instances_to: u64 = to.iter().map(|s| s.instance_count).sum();
+        let bytes_from: u64 = from.iter().map(|s| s.allocation_size_bytes).sum();
+        let bytes_to: u64 = to.iter().map(|s| s.allocation_size_bytes).sum();
+        Self {
+            instances_from,
+            instances_to,
+            delta_instances: instances_to as i64 - instances_from as i64,
+            bytes_from,
+            bytes_to,
+            delta_bytes: bytes_to as i64 - bytes_from as i64,
+        }
     }
 }
 
 // Sums stats per class name: the same class name can appear several times
 // in a dump (same class loaded by multiple classloaders), each occurrence
 // keyed by a different class id.
-fn totals_by_class_name(stats: &[ClassAllocationStats]) -> AHashMap<&str, (u64, u64)> {
+pub fn totals_by_class_name(stats: &[ClassAllocationStats]) -> AHashMap<&str, (u64, u64)> {
     let mut totals: AHashMap<&str, (u64, u64)> = AHashMap::new();
     for s in stats {
         let (instances, bytes) = totals.entry(s.class_name.as_str()).or_default();
@@ -62,18 +108,28 @@ pub fn compute(from: &[ClassAllocationStats], to: &[ClassAllocationStats]) -> Ve
             let (instances_from, bytes_from) =
                 from_by_name.get(class_name).copied().unwrap_or_default();
             let (instances_to, bytes_to) = to_by_name.get(class_name).copied().unwrap_or_default();
+            let change = if !from_by_name.contains_key(class_name) {
+                DiffChange::Added
+            } else if !to_by_name.contains_key(class_name) {
+                DiffChange::Removed
+            } else {
+                DiffChange::Changed
+            };
             DiffEntry {
                 class_name: class_name.to_string(),
+                change,
                 instances_from,
                 instances_to,
+                delta_instances: instances_to as i64 - instances_from as i64,
                 bytes_from,
                 bytes_to,
+                delta_bytes: bytes_to as i64 - bytes_from as i64,
             }
         })
-        .filter(|e| e.delta_bytes() != 0 || e.delta_instances() != 0)
+        .filter(|e| e.delta_bytes != 0 || e.delta_instances != 0)
         .collect();
 
-    entries.sort_by_key(|e| Reverse(e.delta_bytes()));
+    entries.sort_by_key(|e| Reverse(e.delta_bytes));
     entries
 }
 
@@ -85,19 +141,25 @@ pub fn render(
     entries: &[DiffEntry],
     top: usize,
 ) -> String {
-    let total_from: u64 = from.iter().map(|s| s.allocation_size_bytes).sum();
-    let total_to: u64 = to.iter().map(|s| s.allocation_size_bytes).sum();
-    let net = total_to as i64 - total_from as i64;
+    let totals = DiffTotals::new(from, to);
 
     let mut out = String::new();
     let _ = writeln!(out, "\nHeap diff of raw shallow sizes:");
     let _ = writeln!(
         out,
         "  from: {from_label} ({})",
-        pretty_bytes_size(total_from)
+        pretty_bytes_size(totals.bytes_from)
+    );
+    let _ = writeln!(
+        out,
+        "  to:   {to_label} ({})",
+        pretty_bytes_size(totals.bytes_to)
+    );
+    let _ = writeln!(
+        out,
+        "  net:  {}",
+        pretty_signed_bytes_size(totals.delta_bytes)
     );
-    let _ = writeln!(out, "  to:   {to_label} ({})", pretty_bytes_size(total_to));
-    let _ = writeln!(out, "  net:  {}", pretty_signed_bytes_size(net));
 
     if entries.is_empty() {
         let _ = writeln!(out, "\nNo per-class differences between the two dumps.");
@@ -125,8 +187,8 @@ pub fn render(
         let _ = writeln!(
             out,
             "{:>12} {:>12} {:>23} {:>21}  {}",
-            pretty_signed_bytes_size(entry.delta_bytes()),
-            entry.delta_instances(),
+            pretty_signed_bytes_size(entry.delta_bytes),
+            entry.delta_instances,
             size_from_to,
             instances_from_to,
             entry.class_name
@@ -135,9 +197,34 @@ pub fn render(
     out
 }
 
+// One row per changed class, the largest growth first.
+pub fn render_csv(entries: &[DiffEntry]) -> String {
+    let format = TableFormat::Csv;
+    let mut out = String::from(
+        "class_name,change,instances_from,instances_to,delta_instances,bytes_from,bytes_to,delta_bytes\n",
+    );
+    for entry in entries {
+        let _ = writeln!(
+            out,
+            "{},{},{},{},{},{},{},{}",
+            format.field(&entry.class_name),
+            entry.change.name(),
+            entry.instances_from,
+            entry.instances_to,
+            entry.delta_instances,
+            entry.bytes_from,
+            entry.bytes_to,
+            entry.delta_bytes
+        );
+    }
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::args::ReportOptions;
+    use crate::rendered_result::{DumpInfo, JsonDiffResult, diff_json_schema};
     use crate::slurp::slurp_file;
 
     fn stats(class_name: &str, instances: u64, bytes: u64) -> ClassAllocationStats {
@@ -166,12 +253,36 @@ mod tests {
         assert_eq!(names, vec!["Grower", "Added", "Removed", "Shrinker"]);
 
         let grower = &entries[0];
-        assert_eq!(grower.delta_bytes(), 200);
-        assert_eq!(grower.delta_instances(), 20);
+        assert_eq!(grower.delta_bytes, 200);
+        assert_eq!(grower.delta_instances, 20);
 
         let removed = &entries[2];
         assert_eq!(removed.bytes_to, 0);
-        assert_eq!(removed.delta_bytes(), -10);
+        assert_eq!(removed.delta_bytes, -10);
+
+        let changes: Vec<_> = entries.iter().map(|e| e.change).collect();
+        assert_eq!(
+            changes,
+            vec![
+                DiffChange::Changed,
+                DiffChange::Added,
+                DiffChange::Removed,
+                DiffChange::Changed
+            ]
+        );
+    }
+
+    #[test]
+    fn csv_lists_every_entry() {
+        let from = vec![stats("Grower", 10, 100), stats("Removed, old", 1, 10)];
+        let to = vec![stats("Grower", 30, 300)];
+
+        let csv = render_csv(&compute(&from, &to));
+
+        assert_eq!(
+            csv,
+            "class_name,change,instances_from,instances_to,delta_instances,bytes_from,bytes_to,delta_bytes\nGrower,changed,10,30,20,100,300,200\n\"Removed, old\",removed,1,0,-1,10,0,-10\n"
+        );
     }
 
     #[test]
@@ -203,13 +314,23 @@ mod tests {
         assert_eq!(entries.len(), 1);
         assert_eq!(entries[0].bytes_from, 30);
         assert_eq!(entries[0].bytes_to, 40);
-        assert_eq!(entries[0].delta_bytes(), 10);
+        assert_eq!(entries[0].delta_bytes, 10);
     }
 
     #[test]
     fn diff_of_identical_dumps_is_empty() {
-        let (_, from) = slurp_file("test-heap-dumps/hprof-32.bin", false, false).unwrap();
-        let (_, to) = slurp_file("test-heap-dumps/hprof-32.bin", false, false).unwrap();
+        let (_, from) = slurp_file(
+            "test-heap-dumps/hprof-32.bin",
+            false,
+            ReportOptions::default(),
+        )
+        .unwrap();
+        let (_, to) = slurp_file(
+            "test-heap-dumps/hprof-32.bin",
+            false,
+            ReportOptions::default(),
+        )
+        .unwrap();
 
         assert!(compute(&from.memory_usage, &to.memory_usage).is_empty());
     }
@@ -220,8 +341,8 @@ mod tests {
     fn diff_of_different_dumps_matches_gold() {
         let from_path = "test-heap-dumps/hprof-32.bin";
         let to_path = "test-heap-dumps/hprof-64.bin";
-        let (_, from) = slurp_file(from_path, false, false).unwrap();
-        let (_, to) = slurp_file(to_path, false, false).unwrap();
+        let (_, from) = slurp_file(from_path, false, ReportOptions::default()).unwrap();
+        let (_, to) = slurp_file(to_path, false, ReportOptions::default()).unwrap();
 
         let entries = compute(&from.memory_usage, &to.memory_usage);
         let rendered = render(
@@ -237,4 +358,43 @@ mod tests {
             .expect("gold file not found!");
         assert_eq!(rendered, gold);
     }
+
+    #[test]
+    fn json_diff_of_different_dumps_matches_schema() {
+        let dump_info = |file_path: &str| {
+            let (file_header, result) =
+                slurp_file(file_path, false, ReportOptions::default()).unwrap();
+            let dump = DumpInfo::new(
+                file_path.to_string(),
+                std::fs::metadata(file_path).unwrap().len(),
+                file_header.format,
+                file_header.size_pointers,
+                file_header.timestamp,
+            );
+            (dump, result.memory_usage)
+        };
+        let (from_dump, from) = dump_info("test-heap-dumps/hprof-32.bin");
+        let (to_dump, to) = dump_info("test-heap-dumps/hprof-64.bin");
+        let entries = compute(&from, &to);
+        let entry_count = entries.len();
+
+        let json_result =
+            JsonDiffResult::new(from_dump, to_dump, DiffTotals::new(&from, &to), entries);
+        let json = serde_json::to_value(&json_result).unwrap();
+
+        let validator =
+            jsonschema::validator_for(&diff_json_schema()).expect("schema should be valid");
+        let errors: Vec<_> = validator
+            .iter_errors(&json)
+            .map(|error| format!("{} at {}", error, error.instance_path()))
+            .collect();
+        assert!(errors.is_empty(), "{errors:#?}");
+        // every delta, not only the top of the text output
+        assert_eq!(json["entries"].as_array().unwrap().len(), entry_count);
+        assert_eq!(
+            json["totals"]["delta_bytes"],
+            json["totals"]["bytes_to"].as_i64().unwrap()
+                - json["totals"]["bytes_from"].as_i64().unwrap()
+        );
+    }
 }
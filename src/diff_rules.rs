@@ -0,0 +1,336 @@
+//! `diff --max-net-growth`, `--max-class-growth` and `--no-growth` — rules
+//! evaluated over the class deltas so a leak regression fails a CI pipeline:
+//! the violated rules are listed and the process exits with code 3, apart
+//! from the analysis errors (1) and the usage errors reported by clap (2).
+
+use std::fmt::Write;
+
+use crate::diff::{DiffChange, DiffEntry, DiffTotals};
+use crate::rendered_result::ClassAllocationStats;
+use crate::utils::{pluralize, pretty_bytes_size, pretty_signed_bytes_size};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffRule {
+    // the shallow heap must not grow by more than this many bytes
+    MaxNetGrowth(u64),
+    // no class present in both dumps may grow its shallow size by more than
+    // this percentage, added classes are only covered by the net growth
+    MaxClassGrowthPercent(u32),
+    // the instance count of this class must not grow, a class in neither
+    // dump violates it as the name is most likely misspelled
+    NoInstanceGrowth(String),
+}
+
+impl DiffRule {
+    fn describe(&self) -> String {
+        match self {
+            Self::MaxNetGrowth(bytes) => {
+                format!("net growth at most {}", pretty_bytes_size(*bytes))
+            }
+            Self::MaxClassGrowthPercent(percent) => {
+                format!("no class grows by more than {percent}%")
+            }
+            Self::NoInstanceGrowth(class_name) => {
+                format!("{class_name} instances do not grow")
+            }
+        }
+    }
+
+    // One line per offending total or class, the largest growth first, empty
+    // when the rule holds.
+    fn violations(
+        &self,
+        totals: &DiffTotals,
+        entries: &[DiffEntry],
+        is_in_dumps: impl Fn(&str) -> bool,
+    ) -> Vec<String> {
+        match self {
+            Self::MaxNetGrowth(bytes) => {
+                if totals.delta_bytes > 0 && totals.delta_bytes.unsigned_abs() > *bytes {
+                    vec![format!(
+                        "net growth {}",
+                        pretty_signed_bytes_size(totals.delta_bytes)
+                    )]
+                } else {
+                    Vec::new()
+                }
+            }
+            Self::MaxClassGrowthPercent(percent) => {
+                let mut offenders: Vec<_> = entries
+                    .iter()
+                    .filter(|entry| {
+                        entry.change == DiffChange::Changed
+                            && entry.delta_bytes > 0
+                            && u128::from(entry.delta_bytes.unsigned_abs()) * 100
+                                > u128::from(entry.bytes_from) * u128::from(*percent)
+                    })
+                    .collect();
+                offenders.sort_by_key(|entry| std::cmp::Reverse(entry.delta_bytes));
+                offenders
+                    .into_iter()
+                    .map(|entry| {
+                        // e.g. instances without data, any growth exceeds the limit
+                        let growth = if entry.bytes_from == 0 {
+                            "grew from zero bytes".to_string()
+                        } else {
+                            format!(
+                                "grew by {:.1}%",
+                                entry.delta_bytes as f64 * 100.0 / entry.bytes_from as f64
+                            )
+                        };
+                        format!(
+                            "{} {growth} ({} → {})",
+                            entry.class_name,
+                            pretty_bytes_size(entry.bytes_from),
+                            pretty_bytes_size(entry.bytes_to)
+                        )
+                    })
+                    .collect()
+            }
+            Self::NoInstanceGrowth(class_name) => {
+                let Some(entry) = entries.iter().find(|entry| &entry.class_name == class_name)
+                else {
+                    // unchanged classes have no entry
+                    return if is_in_dumps(class_name) {
+                        Vec::new()
+                    } else {
+                        vec![format!("{class_name} not found in either dump")]
+                    };
+                };
+                if entry.delta_instances > 0 {
+                    vec![format!(
+                        "{} instances grew by {} ({} → {})",
+                        entry.class_name,
+                        entry.delta_instances,
+                        entry.instances_from,
+                        entry.instances_to
+                    )]
+                } else {
+                    Vec::new()
+                }
+            }
+        }
+    }
+}
+
+pub struct RuleResult {
+    pub rule: DiffRule,
+    pub violations: Vec<String>,
+}
+
+// `from` and `to` are the histograms `totals` and `entries` are computed from.
+pub fn check(
+    rules: &[DiffRule],
+    from: &[ClassAllocationStats],
+    to: &[ClassAllocationStats],
+    totals: &DiffTotals,
+    entries: &[DiffEntry],
+) -> Vec<RuleResult> {
+    let is_in_dumps = |class_name: &str| {
+        from.iter()
+            .chain(to)
+            .any(|stats| stats.class_name == class_name)
+    };
+    rules
+        .iter()
+        .map(|rule| RuleResult {
+            rule: rule.clone(),
+            violations: rule.violations(totals, entries, is_in_dumps),
+        })
+        .collect()
+}
+
+// Lists the `top` violations of each rule, e.g. the classes growing the most.
+pub fn render(results: &[RuleResult], top: usize) -> String {
+    let failed = results.iter().filter(|r| !r.violations.is_empty()).count();
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "\nChecked {}, {failed} violated:",
+        pluralize(results.len(), "diff rule", "diff rules")
+    );
+    for result in results {
+        let status = if result.violations.is_empty() {
+            "ok"
+        } else {
+            "FAIL"
+        };
+        let _ = writeln!(out, "  {status:<4}  {}", result.rule.describe());
+        for violation in result.violations.iter().take(top) {
+            let _ = writeln!(out, "          {violation}");
+        }
+        if result.violations.len() > top {
+            let remaining = result.violations.len() - top;
+            let _ = writeln!(
+                out,
+                "          ... {}",
+                pluralize(remaining, "more class", "more classes")
+            );
+        }
+    }
+    out
+}
+
+// Byte size with an optional `B`, `KiB`, `MiB` or `GiB` unit, e.g. `50MiB`.
+pub fn parse_size(raw: &str) -> Result<u64, String> {
+    let raw = raw.trim();
+    let unit_start = raw
+        .find(|c: char| c.is_ascii_alphabetic())
+        .unwrap_or(raw.len());
+    let (number, unit) = raw.split_at(unit_start);
+    let multiplier: u64 = match unit.to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "kib" => 1 << 10,
+        "mib" => 1 << 20,
+        "gib" => 1 << 30,
+        _ => {
+            return Err(format!(
+                "unknown size unit `{unit}`, use B, KiB, MiB or GiB"
+            ));
+        }
+    };
+    let number: f64 = number
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid size `{raw}`"))?;
+    if !number.is_finite() || number < 0.0 {
+        return Err(format!("invalid size `{raw}`"));
+    }
+    Ok((number * multiplier as f64) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::compute;
+    use crate::rendered_result::ClassAllocationStats;
+
+    fn stats(class_name: &str, instances: u64, bytes: u64) -> ClassAllocationStats {
+        ClassAllocationStats::new(class_name.to_string(), instances, 0, bytes)
+    }
+
+    #[test]
+    fn sizes_accept_binary_units() {
+        assert_eq!(parse_size("1024"), Ok(1024));
+        assert_eq!(parse_size("50MiB"), Ok(50 * 1024 * 1024));
+        assert_eq!(parse_size("1.5 kib"), Ok(1536));
+        assert!(parse_size("50MB").is_err());
+        assert!(parse_size("-1KiB").is_err());
+    }
+
+    #[test]
+    fn violated_rules_list_the_offending_classes() {
+        let from = vec![
+            stats("Grower", 10, 100),
+            stats("com.acme.Session", 2, 64),
+            stats("Stable", 5, 1000),
+        ];
+        let to = vec![
+            stats("Grower", 30, 300),
+            stats("com.acme.Session", 3, 64),
+            stats("Stable", 5, 1000),
+            stats("Added", 1, 4096),
+        ];
+        let entries = compute(&from, &to);
+        let totals = DiffTotals::new(&from, &to);
+        let rules = vec![
+            DiffRule::MaxNetGrowth(1024 * 1024),
+            DiffRule::MaxClassGrowthPercent(50),
+            DiffRule::NoInstanceGrowth("com.acme.Session".to_string()),
+            DiffRule::NoInstanceGrowth("Stable".to_string()),
+        ];
+
+        let results = check(&rules, &from, &to, &totals, &entries);
+
+        assert_eq!(
+            render(&results, 20),
+            "\nChecked 4 diff rules, 2 violated:
+  ok    net growth at most 1.00MiB
+  FAIL  no class grows by more than 50%
+          Grower grew by 200.0% (100.00bytes → 300.00bytes)
+  FAIL  com.acme.Session instances do not grow
+          com.acme.Session instances grew by 1 (2 → 3)
+  ok    Stable instances do not grow
+"
+        );
+    }
+
+    #[test]
+    fn only_the_top_growing_classes_are_listed() {
+        let from = vec![stats("A", 1, 100), stats("B", 1, 100), stats("C", 1, 100)];
+        let to = vec![stats("A", 1, 200), stats("B", 1, 400), stats("C", 1, 300)];
+        let entries = compute(&from, &to);
+        let totals = DiffTotals::new(&from, &to);
+
+        let results = check(
+            &[DiffRule::MaxClassGrowthPercent(10)],
+            &from,
+            &to,
+            &totals,
+            &entries,
+        );
+
+        assert_eq!(results[0].violations.len(), 3);
+        assert_eq!(
+            render(&results, 2),
+            "\nChecked 1 diff rule, 1 violated:
+  FAIL  no class grows by more than 10%
+          B grew by 300.0% (100.00bytes → 400.00bytes)
+          C grew by 200.0% (100.00bytes → 300.00bytes)
+          ... 1 more class
+"
+        );
+    }
+
+    #[test]
+    fn net_growth_is_checked_against_the_totals() {
+        let from = vec![stats("A", 1, 1024)];
+        let to = vec![stats("A", 1, 4096)];
+        let totals = DiffTotals::new(&from, &to);
+
+        let results = check(
+            &[DiffRule::MaxNetGrowth(2048)],
+            &from,
+            &to,
+            &totals,
+            &compute(&from, &to),
+        );
+        assert_eq!(results[0].violations, vec!["net growth +3.00KiB"]);
+
+        let results = check(
+            &[DiffRule::MaxNetGrowth(3072)],
+            &from,
+            &to,
+            &totals,
+            &compute(&from, &to),
+        );
+        assert!(results[0].violations.is_empty());
+    }
+
+    #[test]
+    fn unknown_classes_and_growth_from_zero_bytes_are_violations() {
+        let from = vec![stats("Empty", 1, 0), stats("Stable", 1, 16)];
+        let to = vec![stats("Empty", 2, 32), stats("Stable", 1, 16)];
+        let rules = vec![
+            DiffRule::MaxClassGrowthPercent(1000),
+            DiffRule::NoInstanceGrowth("Stabel".to_string()),
+        ];
+
+        let results = check(
+            &rules,
+            &from,
+            &to,
+            &DiffTotals::new(&from, &to),
+            &compute(&from, &to),
+        );
+
+        assert_eq!(
+            results[0].violations,
+            vec!["Empty grew from zero bytes (0.00bytes → 32.00bytes)"]
+        );
+        assert_eq!(
+            results[1].violations,
+            vec!["Stabel not found in either dump"]
+        );
+    }
+}
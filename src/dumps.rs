@@ -0,0 +1,135 @@
+//! `--dumps` — legacy agents and some tools append several heap dumps to the
+//! same file. Each dump is reported separately, and with `--diff-dumps`
+//! compared to the previous one, turning the file into a small time series.
+
+use std::fmt::Write;
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::diff;
+use crate::rendered_result::{ClassAllocationStats, RenderedResult};
+use crate::utils::{pluralize, pretty_bytes_size, pretty_timestamp_utc};
+
+#[derive(Serialize, JsonSchema, Clone)]
+pub struct DumpUsage {
+    // capture time in epoch milliseconds, `None` when the file has none
+    pub timestamp: Option<u64>,
+    pub memory_usage: Vec<ClassAllocationStats>,
+}
+
+impl DumpUsage {
+    fn label(&self, index: usize) -> String {
+        match self.timestamp {
+            Some(timestamp) => format!("dump {} at {}", index + 1, pretty_timestamp_utc(timestamp)),
+            None => format!("dump {}", index + 1),
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema, Clone)]
+pub struct DumpReport {
+    pub dumps: Vec<DumpUsage>,
+    // `--diff-dumps`
+    pub diff_with_previous: bool,
+}
+
+impl DumpReport {
+    pub fn render(&mut self, top: usize) -> String {
+        let dumps = &mut self.dumps;
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "\nFound {} in the file:\n",
+            pluralize(dumps.len(), "heap dump", "heap dumps")
+        );
+        for (index, dump) in dumps.iter().enumerate() {
+            let total_size: u64 = dump
+                .memory_usage
+                .iter()
+                .map(|stats| stats.allocation_size_bytes)
+                .sum();
+            let objects: u64 = dump.memory_usage.iter().map(|s| s.instance_count).sum();
+            let _ = writeln!(
+                out,
+                "  {}: {} in {}",
+                dump.label(index),
+                pretty_bytes_size(total_size),
+                pluralize(objects, "object", "objects")
+            );
+        }
+        for (index, dump) in dumps.iter_mut().enumerate() {
+            let _ = writeln!(
+                out,
+                "\nTop {top} raw shallow heap classes in dump {}:\n",
+                index + 1
+            );
+            dump.memory_usage
+                .sort_by_key(|stats| std::cmp::Reverse(stats.allocation_size_bytes));
+            RenderedResult::render_table(top, &mut out, &dump.memory_usage);
+        }
+        if self.diff_with_previous {
+            for (index, pair) in dumps.windows(2).enumerate() {
+                let [from, to] = pair else { continue };
+                let entries = diff::compute(&from.memory_usage, &to.memory_usage);
+                let _ = write!(
+                    out,
+                    "{}",
+                    diff::render(
+                        &from.label(index),
+                        &to.label(index + 1),
+                        &from.memory_usage,
+                        &to.memory_usage,
+                        &entries,
+                        top
+                    )
+                );
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dumps_are_listed_and_diffed_with_the_previous_one() {
+        let stats = |size| {
+            vec![ClassAllocationStats::new(
+                "byte[]".to_string(),
+                1,
+                size,
+                size,
+            )]
+        };
+        let dumps = vec![
+            DumpUsage {
+                timestamp: Some(1_608_192_273_831),
+                memory_usage: stats(16),
+            },
+            DumpUsage {
+                timestamp: None,
+                memory_usage: stats(48),
+            },
+        ];
+
+        let mut report = DumpReport {
+            dumps,
+            diff_with_previous: false,
+        };
+        let rendered = report.render(5);
+        assert!(rendered.starts_with(
+            "\nFound 2 heap dumps in the file:\n\n  dump 1 at 2020-12-17 08:04:33 UTC: 16.00bytes in 1 object\n  dump 2: 48.00bytes in 1 object\n"
+        ));
+        assert!(rendered.contains("Top 5 raw shallow heap classes in dump 2:"));
+        assert!(!rendered.contains("Heap diff"));
+
+        report.diff_with_previous = true;
+        let rendered = report.render(5);
+        assert!(rendered.contains("  from: dump 1 at 2020-12-17 08:04:33 UTC (16.00bytes)\n"));
+        assert!(rendered.contains("  to:   dump 2 (48.00bytes)\n"));
+        assert!(rendered.contains("+32.00bytes"));
+    }
+}
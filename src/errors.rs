@@ -19,4 +19,19 @@ pub enum HprofSlurpError {
     StdThreadError { e: Box<dyn Any + Send + 'static> },
     #[error("serialization error ({0})")]
     SerdeError(#[from] serde_json::Error),
+    #[error("dump `{name}` has no capture time to order it in the trend")]
+    MissingCaptureTime { name: String },
+    #[error("{violated} of the diff rules violated")]
+    DiffRulesViolated { violated: usize },
+}
+
+impl HprofSlurpError {
+    // `3` tells a failed diff rule from a failed analysis and from a usage
+    // error, which clap reports with `2`, in CI.
+    pub const fn exit_code(&self) -> i32 {
+        match self {
+            Self::DiffRulesViolated { .. } => 3,
+            _ => 1,
+        }
+    }
 }
@@ -0,0 +1,212 @@
+//! `--flamegraph` — the histogram as a self-contained SVG icicle chart: the
+//! hierarchy is the package segments then the class, the width the shallow
+//! bytes. Hovering a frame shows its instance count, no script is needed so
+//! the file can be embedded as is in any document.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::descriptor::package_frames;
+use crate::rendered_result::ClassAllocationStats;
+use crate::utils::{escape_markup, pretty_bytes_size};
+
+const WIDTH: f64 = 1200.0;
+const MARGIN: f64 = 10.0;
+const TITLE_HEIGHT: f64 = 40.0;
+const FRAME_HEIGHT: f64 = 18.0;
+const FONT_SIZE: f64 = 12.0;
+// average glyph width used to truncate the labels
+const CHAR_WIDTH: f64 = 7.0;
+// frames narrower than this are left out, their bytes stay in the parent
+const MIN_FRAME_WIDTH: f64 = 0.1;
+
+#[derive(Default)]
+struct Node {
+    // dotted path from the root, shown in the tooltip
+    path: String,
+    bytes: u64,
+    instances: u64,
+    children: BTreeMap<String, Node>,
+}
+
+impl Node {
+    fn build(memory_usage: &[ClassAllocationStats]) -> Self {
+        let mut root = Self::default();
+        for stats in memory_usage {
+            root.bytes += stats.allocation_size_bytes;
+            root.instances += stats.instance_count;
+            let mut node = &mut root;
+            for frame in package_frames(&stats.class_name) {
+                let path = if node.path.is_empty() {
+                    frame.to_string()
+                } else {
+                    format!("{}.{frame}", node.path)
+                };
+                node = node.children.entry(frame.to_string()).or_insert(Self {
+                    path,
+                    ..Self::default()
+                });
+                node.bytes += stats.allocation_size_bytes;
+                node.instances += stats.instance_count;
+            }
+        }
+        root
+    }
+
+    fn depth(&self) -> usize {
+        1 + self.children.values().map(Self::depth).max().unwrap_or(0)
+    }
+}
+
+// Warm color derived from the name so a class keeps its color across graphs.
+fn color(name: &str) -> String {
+    // FNV-1a
+    let hash = name.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    let red = 205 + hash % 50;
+    let green = (hash >> 8) % 230;
+    let blue = (hash >> 16) % 55;
+    format!("rgb({red},{green},{blue})")
+}
+
+// Label fitting in `width`, `None` when fewer than three characters fit.
+fn label(name: &str, width: f64) -> Option<String> {
+    let fitting = ((width - 6.0) / CHAR_WIDTH) as usize;
+    let length = name.chars().count();
+    if fitting < 3 {
+        None
+    } else if length <= fitting {
+        Some(name.to_string())
+    } else {
+        let truncated: String = name.chars().take(fitting - 2).collect();
+        Some(format!("{truncated}.."))
+    }
+}
+
+struct Frame<'a> {
+    name: &'a str,
+    node: &'a Node,
+    x: f64,
+    depth: usize,
+}
+
+fn render_frame(out: &mut String, frame: &Frame, width: f64, total_bytes: u64) {
+    let Frame {
+        name,
+        node,
+        x,
+        depth,
+    } = *frame;
+    let y = TITLE_HEIGHT + depth as f64 * FRAME_HEIGHT;
+    let percent = node.bytes as f64 * 100.0 / total_bytes as f64;
+    let tooltip = if depth == 0 { name } else { &node.path };
+    let _ = write!(
+        out,
+        "<g><title>{}: {} in {} instances ({percent:.2}%)</title><rect x=\"{x:.1}\" y=\"{y:.1}\" width=\"{width:.1}\" height=\"{:.1}\" fill=\"{}\" rx=\"2\" ry=\"2\"/>",
+        escape_markup(tooltip),
+        pretty_bytes_size(node.bytes),
+        node.instances,
+        FRAME_HEIGHT - 1.0,
+        color(name)
+    );
+    if let Some(label) = label(name, width) {
+        let _ = write!(
+            out,
+            "<text x=\"{:.1}\" y=\"{:.1}\">{}</text>",
+            x + 3.0,
+            y + FONT_SIZE + 1.0,
+            escape_markup(&label)
+        );
+    }
+    let _ = writeln!(out, "</g>");
+}
+
+pub fn render(memory_usage: &[ClassAllocationStats], title: &str) -> String {
+    let root = Node::build(memory_usage);
+    let height = TITLE_HEIGHT + root.depth() as f64 * FRAME_HEIGHT + MARGIN;
+    let mut out = String::new();
+    let _ = writeln!(out, "<?xml version=\"1.0\" standalone=\"no\"?>");
+    let _ = writeln!(
+        out,
+        "<svg version=\"1.1\" width=\"{WIDTH}\" height=\"{height}\" viewBox=\"0 0 {WIDTH} {height}\" xmlns=\"http://www.w3.org/2000/svg\" font-family=\"Verdana, sans-serif\" font-size=\"{FONT_SIZE}\">"
+    );
+    let _ = writeln!(
+        out,
+        "<rect x=\"0\" y=\"0\" width=\"{WIDTH}\" height=\"{height}\" fill=\"#f8f8f8\"/>"
+    );
+    let _ = writeln!(
+        out,
+        "<text x=\"{}\" y=\"24\" font-size=\"17\" text-anchor=\"middle\">{}</text>",
+        WIDTH / 2.0,
+        escape_markup(title)
+    );
+    if root.bytes > 0 {
+        let scale = (WIDTH - 2.0 * MARGIN) / root.bytes as f64;
+        // depth first, the largest children on the left
+        let mut pending = vec![Frame {
+            name: "all",
+            node: &root,
+            x: MARGIN,
+            depth: 0,
+        }];
+        while let Some(frame) = pending.pop() {
+            render_frame(
+                &mut out,
+                &frame,
+                frame.node.bytes as f64 * scale,
+                root.bytes,
+            );
+            let mut children: Vec<_> = frame.node.children.iter().collect();
+            children.sort_by_key(|(_, child)| std::cmp::Reverse(child.bytes));
+            let mut x = frame.x;
+            for (name, child) in children {
+                let width = child.bytes as f64 * scale;
+                if width >= MIN_FRAME_WIDTH {
+                    pending.push(Frame {
+                        name,
+                        node: child,
+                        x,
+                        depth: frame.depth + 1,
+                    });
+                }
+                x += width;
+            }
+        }
+    }
+    let _ = writeln!(out, "</svg>");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_are_sized_by_shallow_bytes() {
+        let memory_usage = vec![
+            ClassAllocationStats::new("java.lang.String".to_string(), 3, 24, 72),
+            ClassAllocationStats::new("java.lang.Object".to_string(), 2, 16, 32),
+            ClassAllocationStats::new("byte[]".to_string(), 1, 96, 96),
+        ];
+
+        let svg = render(&memory_usage, "heap <dump>");
+
+        assert!(svg.starts_with("<?xml"));
+        assert!(svg.ends_with("</svg>\n"));
+        assert!(svg.contains(">heap &lt;dump&gt;</text>"));
+        assert!(svg.contains("<title>all: 200.00bytes in 6 instances (100.00%)</title><rect x=\"10.0\" y=\"40.0\" width=\"1180.0\""));
+        // `java.lang` holds 104 of the 200 bytes, left of the smaller `byte[]`
+        assert!(svg.contains("<title>java.lang: 104.00bytes in 5 instances (52.00%)</title><rect x=\"10.0\" y=\"76.0\" width=\"613.6\""));
+        assert!(
+            svg.contains("<title>java.lang.String: 72.00bytes in 3 instances (36.00%)</title>")
+        );
+    }
+
+    #[test]
+    fn labels_are_truncated_to_the_frame_width() {
+        assert_eq!(label("String", 100.0).as_deref(), Some("String"));
+        assert_eq!(label("ConcurrentHashMap", 50.0).as_deref(), Some("Conc.."));
+        assert_eq!(label("String", 20.0), None);
+    }
+}
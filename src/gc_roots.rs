@@ -0,0 +1,361 @@
+//! `--gc-roots` — per GC root type, the number of roots and the classes and
+//! shallow size of the objects they directly reference.
+
+use std::fmt::Write;
+
+use ahash::{AHashMap, AHashSet};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::parser::gc_record::{FieldType, GcRecord};
+use crate::utils::{pluralize, pretty_bytes_size};
+
+#[derive(Serialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum RootKind {
+    Unknown,
+    ThreadObject,
+    JniGlobal,
+    JniLocal,
+    JavaFrame,
+    NativeStack,
+    StickyClass,
+    ThreadBlock,
+    MonitorUsed,
+    // Android HPROF 1.0.3 extensions
+    InternedString,
+    Finalizing,
+    Debugger,
+    ReferenceCleanup,
+    VmInternal,
+    JniMonitor,
+    Unreachable,
+}
+
+impl RootKind {
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Unknown => "unknown",
+            Self::ThreadObject => "thread object",
+            Self::JniGlobal => "JNI global",
+            Self::JniLocal => "JNI local",
+            Self::JavaFrame => "Java frame",
+            Self::NativeStack => "native stack",
+            Self::StickyClass => "sticky class",
+            Self::ThreadBlock => "thread block",
+            Self::MonitorUsed => "monitor used",
+            Self::InternedString => "interned string",
+            Self::Finalizing => "finalizing",
+            Self::Debugger => "debugger",
+            Self::ReferenceCleanup => "reference cleanup",
+            Self::VmInternal => "VM internal",
+            Self::JniMonitor => "JNI monitor",
+            Self::Unreachable => "unreachable",
+        }
+    }
+}
+
+// `-1` (u32::MAX) marks a root without stack frame.
+const NO_FRAME: u32 = u32::MAX;
+
+#[derive(Debug, Clone, Copy)]
+pub struct GcRoot {
+    pub kind: RootKind,
+    pub object_id: u64,
+    pub thread_serial_number: Option<u32>,
+    pub frame_number: Option<u32>,
+}
+
+impl GcRoot {
+    const fn new(kind: RootKind, object_id: u64) -> Self {
+        Self {
+            kind,
+            object_id,
+            thread_serial_number: None,
+            frame_number: None,
+        }
+    }
+
+    const fn on_thread(mut self, thread_serial_number: u32) -> Self {
+        self.thread_serial_number = Some(thread_serial_number);
+        self
+    }
+
+    const fn in_frame(mut self, thread_serial_number: u32, frame_number: u32) -> Self {
+        self.thread_serial_number = Some(thread_serial_number);
+        if frame_number != NO_FRAME {
+            self.frame_number = Some(frame_number);
+        }
+        self
+    }
+
+    pub const fn from_record(gc_record: &GcRecord) -> Option<Self> {
+        let root = match *gc_record {
+            GcRecord::RootUnknown { object_id } => Self::new(RootKind::Unknown, object_id),
+            GcRecord::RootThreadObject {
+                thread_object_id,
+                thread_sequence_number,
+                ..
+            } => Self::new(RootKind::ThreadObject, thread_object_id)
+                .on_thread(thread_sequence_number),
+            GcRecord::RootJniGlobal { object_id, .. } => Self::new(RootKind::JniGlobal, object_id),
+            GcRecord::RootJniLocal {
+                object_id,
+                thread_serial_number,
+                frame_number_in_stack_trace,
+            } => Self::new(RootKind::JniLocal, object_id)
+                .in_frame(thread_serial_number, frame_number_in_stack_trace),
+            GcRecord::RootJavaFrame {
+                object_id,
+                thread_serial_number,
+                frame_number_in_stack_trace,
+            } => Self::new(RootKind::JavaFrame, object_id)
+                .in_frame(thread_serial_number, frame_number_in_stack_trace),
+            GcRecord::RootNativeStack {
+                object_id,
+                thread_serial_number,
+            } => Self::new(RootKind::NativeStack, object_id).on_thread(thread_serial_number),
+            GcRecord::RootStickyClass { object_id } => Self::new(RootKind::StickyClass, object_id),
+            GcRecord::RootThreadBlock {
+                object_id,
+                thread_serial_number,
+            } => Self::new(RootKind::ThreadBlock, object_id).on_thread(thread_serial_number),
+            GcRecord::RootMonitorUsed { object_id } => Self::new(RootKind::MonitorUsed, object_id),
+            GcRecord::RootInternedString { object_id } => {
+                Self::new(RootKind::InternedString, object_id)
+            }
+            GcRecord::RootFinalizing { object_id } => Self::new(RootKind::Finalizing, object_id),
+            GcRecord::RootDebugger { object_id } => Self::new(RootKind::Debugger, object_id),
+            GcRecord::RootReferenceCleanup { object_id } => {
+                Self::new(RootKind::ReferenceCleanup, object_id)
+            }
+            GcRecord::RootVmInternal { object_id } => Self::new(RootKind::VmInternal, object_id),
+            GcRecord::RootJniMonitor {
+                object_id,
+                thread_serial_number,
+                frame_number_in_stack_trace,
+            } => Self::new(RootKind::JniMonitor, object_id)
+                .in_frame(thread_serial_number, frame_number_in_stack_trace),
+            GcRecord::Unreachable { object_id } => Self::new(RootKind::Unreachable, object_id),
+            GcRecord::HeapDumpInfo { .. }
+            | GcRecord::PrimitiveArrayNoDataDump { .. }
+            | GcRecord::InstanceDump { .. }
+            | GcRecord::ObjectArrayDump { .. }
+            | GcRecord::PrimitiveArrayDump { .. }
+            | GcRecord::ClassDump(_) => return None,
+        };
+        Some(root)
+    }
+}
+
+// Enough of a heap object to resolve its class and shallow size later on,
+// kept small as there is one per object in the dump.
+#[derive(Debug, Clone, Copy)]
+pub enum ObjectShape {
+    Instance {
+        class_object_id: u64,
+    },
+    ObjectArray {
+        array_class_id: u64,
+        number_of_elements: u32,
+    },
+    PrimitiveArray {
+        element_type: FieldType,
+        number_of_elements: u32,
+    },
+    // body omitted by the dumper, not attributed to this dump
+    PrimitiveArrayNoData {
+        element_type: FieldType,
+    },
+    // Class objects are not sized, like in the class histogram.
+    Class,
+}
+
+#[derive(Serialize, JsonSchema, Debug, Default, Clone)]
+pub struct RootedClassStats {
+    pub class_name: String,
+    pub objects: u64,
+    pub shallow_size_bytes: u64,
+}
+
+#[derive(Serialize, JsonSchema, Debug, Clone)]
+pub struct RootTypeStats {
+    pub kind: RootKind,
+    pub roots: u64,
+    pub objects: u64,
+    // rooted objects without dump record
+    pub unresolved_objects: u64,
+    pub shallow_size_bytes: u64,
+    pub classes: Vec<RootedClassStats>,
+}
+
+#[derive(Serialize, JsonSchema, Clone)]
+pub struct GcRootReport {
+    pub root_types: Vec<RootTypeStats>,
+}
+
+impl GcRootReport {
+    // `describe` resolves a rooted object into its class name and shallow
+    // size, `None` when the object is not in the dump.
+    pub fn new<F>(roots: &[GcRoot], mut describe: F) -> Self
+    where
+        F: FnMut(u64) -> Option<(String, u64)>,
+    {
+        let mut roots_by_kind: AHashMap<RootKind, (u64, AHashSet<u64>)> = AHashMap::new();
+        for root in roots {
+            let (count, objects) = roots_by_kind.entry(root.kind).or_default();
+            *count += 1;
+            objects.insert(root.object_id);
+        }
+
+        let mut root_types: Vec<RootTypeStats> = roots_by_kind
+            .into_iter()
+            .map(|(kind, (roots, objects))| {
+                let mut unresolved_objects = 0;
+                let mut classes: AHashMap<String, RootedClassStats> = AHashMap::new();
+                for object_id in &objects {
+                    match describe(*object_id) {
+                        Some((class_name, shallow_size_bytes)) => {
+                            let stats = classes.entry(class_name).or_default();
+                            stats.objects += 1;
+                            stats.shallow_size_bytes += shallow_size_bytes;
+                        }
+                        None => unresolved_objects += 1,
+                    }
+                }
+                let mut classes: Vec<RootedClassStats> = classes
+                    .into_iter()
+                    .map(|(class_name, stats)| RootedClassStats {
+                        class_name,
+                        ..stats
+                    })
+                    .collect();
+                classes.sort_unstable_by(|a, b| {
+                    b.shallow_size_bytes
+                        .cmp(&a.shallow_size_bytes)
+                        .then(b.objects.cmp(&a.objects))
+                        .then_with(|| a.class_name.cmp(&b.class_name))
+                });
+                RootTypeStats {
+                    kind,
+                    roots,
+                    objects: objects.len() as u64,
+                    unresolved_objects,
+                    shallow_size_bytes: classes.iter().map(|c| c.shallow_size_bytes).sum(),
+                    classes,
+                }
+            })
+            .collect();
+        root_types.sort_unstable_by_key(|stats| stats.kind);
+        Self { root_types }
+    }
+
+    pub fn render(&self, top: usize) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "\nGC roots and their directly referenced objects:");
+        if self.root_types.is_empty() {
+            let _ = writeln!(out, "\n  none found");
+        }
+        for stats in &self.root_types {
+            let _ = writeln!(
+                out,
+                "\nGC root {}: {} referencing {} ({})",
+                stats.kind.label(),
+                pluralize(stats.roots, "root", "roots"),
+                pluralize(stats.objects, "object", "objects"),
+                pretty_bytes_size(stats.shallow_size_bytes)
+            );
+            if stats.unresolved_objects > 0 {
+                let _ = writeln!(
+                    out,
+                    "  {} not found in the dump",
+                    pluralize(stats.unresolved_objects, "object", "objects")
+                );
+            }
+            for class in stats.classes.iter().take(top) {
+                let _ = writeln!(
+                    out,
+                    "{:>12} {:>10}  {}",
+                    pretty_bytes_size(class.shallow_size_bytes),
+                    class.objects,
+                    class.class_name
+                );
+            }
+            if stats.classes.len() > top {
+                let _ = writeln!(
+                    out,
+                    "  ... {}",
+                    pluralize(stats.classes.len() - top, "more class", "more classes")
+                );
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_roots_keep_thread_and_frame() {
+        let root = GcRoot::from_record(&GcRecord::RootJavaFrame {
+            object_id: 1,
+            thread_serial_number: 2,
+            frame_number_in_stack_trace: 3,
+        })
+        .expect("should be a root");
+        assert_eq!(root.kind, RootKind::JavaFrame);
+        assert_eq!(root.thread_serial_number, Some(2));
+        assert_eq!(root.frame_number, Some(3));
+
+        let root = GcRoot::from_record(&GcRecord::RootJniMonitor {
+            object_id: 1,
+            thread_serial_number: 2,
+            frame_number_in_stack_trace: u32::MAX,
+        })
+        .expect("should be a root");
+        assert_eq!(root.frame_number, None);
+
+        assert!(
+            GcRoot::from_record(&GcRecord::HeapDumpInfo {
+                heap_type: 0,
+                heap_name_id: 0
+            })
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn report_groups_rooted_objects_by_kind_and_class() {
+        let roots = [
+            GcRoot::new(RootKind::JniGlobal, 1),
+            GcRoot::new(RootKind::JniGlobal, 2),
+            // same object rooted twice is counted once
+            GcRoot::new(RootKind::JniGlobal, 2),
+            GcRoot::new(RootKind::JniGlobal, 3),
+            GcRoot::new(RootKind::InternedString, 4),
+        ];
+        let report = GcRootReport::new(&roots, |object_id| match object_id {
+            1 | 2 => Some(("java.lang.String".to_string(), 24)),
+            4 => Some(("java.lang.String".to_string(), 24)),
+            _ => None,
+        });
+
+        assert_eq!(report.root_types.len(), 2);
+        let jni_global = &report.root_types[0];
+        assert_eq!(jni_global.kind, RootKind::JniGlobal);
+        assert_eq!(jni_global.roots, 4);
+        assert_eq!(jni_global.objects, 3);
+        assert_eq!(jni_global.unresolved_objects, 1);
+        assert_eq!(jni_global.shallow_size_bytes, 48);
+        assert_eq!(jni_global.classes[0].objects, 2);
+
+        let rendered = report.render(20);
+        assert!(
+            rendered.contains("GC root JNI global: 4 roots referencing 3 objects (48.00bytes)")
+        );
+        assert!(rendered.contains("1 object not found in the dump"));
+        assert!(rendered.contains("GC root interned string: 1 root referencing"));
+    }
+}
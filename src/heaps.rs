@@ -0,0 +1,138 @@
+//! `--heaps` — Android dumps annotate their objects with the heap holding
+//! them: the app heap, the zygote heap shared by all apps forked from it and
+//! the boot image heap. The histogram is reported per heap, and
+//! `--exclude-shared-heaps` leaves the zygote and image heaps out of the totals.
+
+use std::fmt::Write;
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::rendered_result::{ClassAllocationStats, RenderedResult};
+use crate::utils::{pluralize, pretty_bytes_size};
+
+// Heap of the objects not preceded by a heap dump info record.
+pub const DEFAULT_HEAP_TYPE: u32 = 0;
+const APP_HEAP_TYPE: u32 = b'A' as u32;
+const ZYGOTE_HEAP_TYPE: u32 = b'Z' as u32;
+const IMAGE_HEAP_TYPE: u32 = b'I' as u32;
+
+// Heaps populated before the app started, shared with the other processes.
+pub const fn is_shared_heap(heap_type: u32) -> bool {
+    matches!(heap_type, ZYGOTE_HEAP_TYPE | IMAGE_HEAP_TYPE)
+}
+
+// Name used when the heap name string is not in the dump.
+pub fn default_heap_name(heap_type: u32) -> String {
+    match heap_type {
+        DEFAULT_HEAP_TYPE => "default".to_string(),
+        APP_HEAP_TYPE => "app".to_string(),
+        ZYGOTE_HEAP_TYPE => "zygote".to_string(),
+        IMAGE_HEAP_TYPE => "image".to_string(),
+        other => format!("heap {other}"),
+    }
+}
+
+#[derive(Serialize, JsonSchema, Clone)]
+pub struct HeapUsage {
+    pub name: String,
+    pub shared: bool,
+    // left out of the totals by `--exclude-shared-heaps`
+    pub excluded: bool,
+    pub memory_usage: Vec<ClassAllocationStats>,
+}
+
+impl HeapUsage {
+    fn total_size_bytes(&self) -> u64 {
+        self.memory_usage
+            .iter()
+            .map(|stats| stats.allocation_size_bytes)
+            .sum()
+    }
+
+    fn objects(&self) -> u64 {
+        self.memory_usage
+            .iter()
+            .map(|stats| stats.instance_count)
+            .sum()
+    }
+
+    pub fn render(heaps: &mut [Self], top: usize) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "\nFound {}:\n",
+            pluralize(heaps.len(), "heap", "heaps")
+        );
+        for heap in heaps.iter() {
+            let note = match (heap.shared, heap.excluded) {
+                (true, true) => " (shared, excluded from the totals)",
+                (true, false) => " (shared)",
+                _ => "",
+            };
+            let _ = writeln!(
+                out,
+                "  {}: {} in {}{note}",
+                heap.name,
+                pretty_bytes_size(heap.total_size_bytes()),
+                pluralize(heap.objects(), "object", "objects")
+            );
+        }
+        for heap in heaps {
+            let _ = writeln!(
+                out,
+                "\nTop {top} raw shallow heap classes in heap {}:\n",
+                heap.name
+            );
+            heap.memory_usage
+                .sort_by_key(|stats| std::cmp::Reverse(stats.allocation_size_bytes));
+            RenderedResult::render_table(top, &mut out, &heap.memory_usage);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heaps_are_named_and_flagged_shared() {
+        assert_eq!(default_heap_name(u32::from(b'A')), "app");
+        assert_eq!(default_heap_name(DEFAULT_HEAP_TYPE), "default");
+        assert_eq!(default_heap_name(7), "heap 7");
+        assert!(is_shared_heap(u32::from(b'Z')));
+        assert!(is_shared_heap(u32::from(b'I')));
+        assert!(!is_shared_heap(u32::from(b'A')));
+    }
+
+    #[test]
+    fn render_lists_heaps_then_their_histograms() {
+        let stats = |name: &str, size| ClassAllocationStats::new(name.to_string(), 2, size, size);
+        let mut heaps = vec![
+            HeapUsage {
+                name: "app".to_string(),
+                shared: false,
+                excluded: false,
+                memory_usage: vec![stats("byte[]", 16), stats("char[]", 48)],
+            },
+            HeapUsage {
+                name: "zygote".to_string(),
+                shared: true,
+                excluded: true,
+                memory_usage: vec![stats("java.lang.String", 24)],
+            },
+        ];
+
+        let rendered = HeapUsage::render(&mut heaps, 1);
+
+        assert!(rendered.starts_with("\nFound 2 heaps:\n\n  app: 64.00bytes in 4 objects\n"));
+        assert!(
+            rendered
+                .contains("  zygote: 24.00bytes in 2 objects (shared, excluded from the totals)\n")
+        );
+        assert!(rendered.contains("Top 1 raw shallow heap classes in heap app:"));
+        assert!(rendered.contains("| char[]     |"));
+        assert!(!rendered.contains("byte[]"));
+    }
+}
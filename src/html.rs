@@ -0,0 +1,151 @@
+//! `--html` — the report as a single offline HTML file to attach to a ticket:
+//! dump metadata, summary, a sortable and filterable table of every class,
+//! thread stacks, duplicated strings and warnings. Styles and script are
+//! inlined, nothing is fetched when the file is opened.
+
+use std::fmt::Write;
+
+use crate::rendered_result::{ClassAllocationStats, DumpInfo, RenderedResult};
+use crate::utils::{escape_markup, pretty_bytes_size};
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; color: #222; }
+h1 { font-size: 1.5em; }
+h2 { font-size: 1.2em; margin-top: 2em; border-bottom: 1px solid #ccc; }
+pre { background: #f6f6f6; padding: 1em; overflow-x: auto; }
+table { border-collapse: collapse; }
+th, td { padding: 0.2em 0.8em; border-bottom: 1px solid #e4e4e4; text-align: left; }
+td.number, th.number { text-align: right; }
+#histogram th { cursor: pointer; user-select: none; background: #eee; }
+#filter { margin-bottom: 0.5em; width: 30em; }
+";
+
+// Sorts the histogram on a header click, on `data-value` for numbers, and
+// hides the rows not matching the filter.
+const SCRIPT: &str = "
+const table = document.getElementById('histogram');
+const body = table.tBodies[0];
+let sorted = { column: 3, descending: true };
+table.querySelectorAll('th').forEach((th, column) => th.addEventListener('click', () => {
+  const descending = sorted.column === column ? !sorted.descending : column > 0;
+  const key = row => {
+    const cell = row.cells[column];
+    return cell.dataset.value === undefined ? cell.textContent : Number(cell.dataset.value);
+  };
+  const rows = Array.from(body.rows).sort((a, b) => {
+    const [x, y] = [key(a), key(b)];
+    const order = x < y ? -1 : x > y ? 1 : 0;
+    return descending ? -order : order;
+  });
+  rows.forEach(row => body.appendChild(row));
+  sorted = { column, descending };
+}));
+document.getElementById('filter').addEventListener('input', event => {
+  const filter = event.target.value.toLowerCase();
+  for (const row of body.rows) {
+    row.hidden = !row.cells[0].textContent.toLowerCase().includes(filter);
+  }
+});
+";
+
+fn render_section(out: &mut String, title: &str, text: &str) {
+    let _ = writeln!(out, "<h2>{title}</h2>");
+    let _ = writeln!(out, "<pre>{}</pre>", escape_markup(text.trim_matches('\n')));
+}
+
+fn render_histogram(out: &mut String, memory_usage: &[ClassAllocationStats]) {
+    let mut rows: Vec<_> = memory_usage.iter().collect();
+    rows.sort_by_key(|stats| std::cmp::Reverse(stats.allocation_size_bytes));
+    let _ = writeln!(out, "<h2>Classes</h2>");
+    let _ = writeln!(
+        out,
+        "<input id=\"filter\" type=\"search\" placeholder=\"Filter classes\">"
+    );
+    let _ = writeln!(
+        out,
+        "<table id=\"histogram\">\n<thead><tr><th>Class</th><th class=\"number\">Instances</th><th class=\"number\">Largest instance</th><th class=\"number\">Total size</th></tr></thead>\n<tbody>"
+    );
+    for stats in rows {
+        let _ = writeln!(
+            out,
+            "<tr><td>{}</td><td class=\"number\" data-value=\"{}\">{}</td><td class=\"number\" data-value=\"{}\">{}</td><td class=\"number\" data-value=\"{}\">{}</td></tr>",
+            escape_markup(&stats.class_name),
+            stats.instance_count,
+            stats.instance_count,
+            stats.largest_allocation_bytes,
+            pretty_bytes_size(stats.largest_allocation_bytes),
+            stats.allocation_size_bytes,
+            pretty_bytes_size(stats.allocation_size_bytes)
+        );
+    }
+    let _ = writeln!(out, "</tbody>\n</table>");
+}
+
+pub fn render(dump: &DumpInfo, result: &RenderedResult) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "<!DOCTYPE html>\n<html lang=\"en\">\n<head>");
+    let _ = writeln!(out, "<meta charset=\"utf-8\">");
+    let _ = writeln!(
+        out,
+        "<title>hprof-slurp {}</title>",
+        escape_markup(env!("CARGO_PKG_VERSION"))
+    );
+    let _ = writeln!(out, "<style>{STYLE}</style>\n</head>\n<body>");
+    let _ = writeln!(out, "<h1>hprof-slurp report</h1>");
+
+    let _ = writeln!(out, "<table>");
+    for (label, value) in dump.fields() {
+        let _ = writeln!(
+            out,
+            "<tr><th>{label}</th><td>{}</td></tr>",
+            escape_markup(&value)
+        );
+    }
+    let _ = writeln!(out, "</table>");
+
+    render_section(&mut out, "Summary", &result.summary);
+    if let Some(warnings) = &result.warnings {
+        render_section(&mut out, "Warnings", warnings);
+    }
+    render_histogram(&mut out, &result.memory_usage);
+    render_section(&mut out, "Threads", &result.thread_info);
+    if let Some(duplicated_strings) = &result.duplicated_strings {
+        render_section(&mut out, "Duplicated strings", &duplicated_strings.render());
+    }
+
+    let _ = writeln!(out, "<script>{SCRIPT}</script>\n</body>\n</html>");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_class_is_listed_and_escaped() {
+        let dump = DumpInfo::new(
+            "dump.hprof".to_string(),
+            2048,
+            "JAVA PROFILE 1.0.2".to_string(),
+            8,
+            1_608_192_273_831,
+        );
+        let memory_usage = vec![
+            ClassAllocationStats::new("java.lang.String".to_string(), 3, 24, 72),
+            ClassAllocationStats::new("<unknown class 0x1>".to_string(), 1, 96, 96),
+        ];
+
+        let mut out = String::new();
+        render_histogram(&mut out, &memory_usage);
+        assert!(out.contains(
+            "<tr><td>&lt;unknown class 0x1&gt;</td><td class=\"number\" data-value=\"1\">1</td><td class=\"number\" data-value=\"96\">96.00bytes</td><td class=\"number\" data-value=\"96\">96.00bytes</td></tr>\n<tr><td>java.lang.String</td>"
+        ));
+
+        let fields = dump.fields();
+        assert_eq!(fields[1], ("File size", "2.00KiB".to_string()));
+        assert_eq!(
+            fields[4],
+            ("Captured at", "2020-12-17 08:04:33 UTC".to_string())
+        );
+    }
+}
@@ -0,0 +1,240 @@
+//! `--jstack <FILE>` — the threads of the dump rendered in the `jstack` thread
+//! dump format, for existing thread dump analyzers. The heap dump has no
+//! thread state, priority nor native id: the state is inferred from the top
+//! frame and the other header fields are placeholders.
+
+use std::fmt::Write;
+
+use crate::utils::pretty_timestamp_utc;
+
+#[derive(Debug)]
+pub enum JstackFrame {
+    Known {
+        class_name: String,
+        method_name: String,
+        file_name: Option<String>,
+        // same encoding as `StackFrameData::line_number`
+        line_number: i32,
+    },
+    Unknown {
+        stack_frame_id: u64,
+    },
+}
+
+impl JstackFrame {
+    fn render(&self) -> String {
+        match self {
+            Self::Known {
+                class_name,
+                method_name,
+                file_name,
+                line_number,
+            } => {
+                let location = match (file_name, *line_number) {
+                    (_, -3) => "Native Method".to_string(),
+                    (_, -2) => "Compiled Code".to_string(),
+                    (None, _) => "Unknown Source".to_string(),
+                    (Some(file_name), number) if number > 0 => format!("{file_name}:{number}"),
+                    (Some(file_name), _) => file_name.clone(),
+                };
+                format!("{class_name}.{method_name}({location})")
+            }
+            Self::Unknown { stack_frame_id } => {
+                format!("<unknown stack frame 0x{stack_frame_id:x}>(Unknown Source)")
+            }
+        }
+    }
+
+    fn is_method(&self, class: &str, method: &str) -> bool {
+        matches!(self, Self::Known { class_name, method_name, .. } if class_name == class && method_name == method)
+    }
+}
+
+#[derive(Debug)]
+pub struct JstackMonitor {
+    pub object_id: u64,
+    // `None` when the object is not in the dump
+    pub class_name: Option<String>,
+}
+
+impl JstackMonitor {
+    fn render(&self) -> String {
+        let class_name = self.class_name.as_deref().unwrap_or("<unknown class>");
+        format!("<0x{:016x}> (a {class_name})", self.object_id)
+    }
+}
+
+#[derive(Debug)]
+pub struct JstackThread {
+    pub name: String,
+    pub thread_serial_number: u32,
+    pub thread_object_id: Option<u64>,
+    pub frames: Vec<JstackFrame>,
+    // monitor waited on from the top frame
+    pub waiting_on: Option<JstackMonitor>,
+    // (frame number, monitor) locked by that frame
+    pub locked: Vec<(usize, JstackMonitor)>,
+}
+
+impl JstackThread {
+    // `(header state, java.lang.Thread.State)` inferred from the top frame
+    fn state(&self) -> (&'static str, &'static str) {
+        let Some(top_frame) = self.frames.first() else {
+            return ("runnable", "RUNNABLE");
+        };
+        if top_frame.is_method("java.lang.Object", "wait") {
+            ("in Object.wait()", "WAITING (on object monitor)")
+        } else if top_frame.is_method("java.lang.Thread", "sleep") {
+            ("waiting on condition", "TIMED_WAITING (sleeping)")
+        } else if top_frame.is_method("sun.misc.Unsafe", "park")
+            || top_frame.is_method("jdk.internal.misc.Unsafe", "park")
+        {
+            ("waiting on condition", "WAITING (parking)")
+        } else {
+            ("runnable", "RUNNABLE")
+        }
+    }
+
+    fn render(&self, out: &mut String) {
+        let (header_state, thread_state) = self.state();
+        let _ = writeln!(
+            out,
+            "\"{}\" #{} prio=5 os_prio=0 tid=0x{:016x} nid=0x{:x} {header_state} [0x0000000000000000]",
+            self.name,
+            self.thread_serial_number,
+            self.thread_object_id.unwrap_or_default(),
+            self.thread_serial_number
+        );
+        let _ = writeln!(out, "   java.lang.Thread.State: {thread_state}");
+        for (frame_number, frame) in self.frames.iter().enumerate() {
+            let _ = writeln!(out, "\tat {}", frame.render());
+            if frame_number == 0
+                && let Some(monitor) = &self.waiting_on
+            {
+                let _ = writeln!(out, "\t- waiting on {}", monitor.render());
+            }
+            for (_, monitor) in self.locked.iter().filter(|(n, _)| *n == frame_number) {
+                let _ = writeln!(out, "\t- locked {}", monitor.render());
+            }
+        }
+        let _ = writeln!(out);
+    }
+}
+
+pub fn render(timestamp: u64, threads: &[JstackThread]) -> String {
+    let mut out = String::new();
+    // `0` means the dumper did not record a capture time
+    if timestamp != 0 {
+        let _ = writeln!(out, "{}", pretty_timestamp_utc(timestamp));
+    }
+    let _ = writeln!(
+        out,
+        "Full thread dump {} {} (from heap dump):\n",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+    );
+    for thread in threads {
+        thread.render(&mut out);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(class_name: &str, method_name: &str, line_number: i32) -> JstackFrame {
+        JstackFrame::Known {
+            class_name: class_name.to_string(),
+            method_name: method_name.to_string(),
+            file_name: Some("Object.java".to_string()),
+            line_number,
+        }
+    }
+
+    #[test]
+    fn frames_use_jstack_locations() {
+        assert_eq!(
+            frame("java.lang.Object", "wait", -3).render(),
+            "java.lang.Object.wait(Native Method)"
+        );
+        assert_eq!(
+            frame("java.lang.Object", "wait", 502).render(),
+            "java.lang.Object.wait(Object.java:502)"
+        );
+        assert_eq!(
+            frame("java.lang.Object", "wait", -1).render(),
+            "java.lang.Object.wait(Object.java)"
+        );
+        let no_file = JstackFrame::Known {
+            class_name: "A".to_string(),
+            method_name: "b".to_string(),
+            file_name: None,
+            line_number: 3,
+        };
+        assert_eq!(no_file.render(), "A.b(Unknown Source)");
+    }
+
+    #[test]
+    fn waiting_thread_renders_state_and_monitors() {
+        let monitor = || JstackMonitor {
+            object_id: 0xd6ff_9e98,
+            class_name: Some("simple.Data".to_string()),
+        };
+        let thread = JstackThread {
+            name: "Thread 1".to_string(),
+            thread_serial_number: 3,
+            thread_object_id: Some(0xd6ff_d9d8),
+            frames: vec![
+                frame("java.lang.Object", "wait", -3),
+                frame("java.lang.Object", "wait", 502),
+                frame("simple.Data", "get", 47),
+            ],
+            waiting_on: Some(monitor()),
+            locked: vec![(2, monitor())],
+        };
+
+        let rendered = render(1_608_192_273_831, &[thread]);
+
+        assert_eq!(
+            rendered,
+            concat!(
+                "2020-12-17 08:04:33 UTC\n",
+                "Full thread dump hprof-slurp ",
+                env!("CARGO_PKG_VERSION"),
+                " (from heap dump):\n\n",
+                "\"Thread 1\" #3 prio=5 os_prio=0 tid=0x00000000d6ffd9d8 nid=0x3 in Object.wait() [0x0000000000000000]\n",
+                "   java.lang.Thread.State: WAITING (on object monitor)\n",
+                "\tat java.lang.Object.wait(Native Method)\n",
+                "\t- waiting on <0x00000000d6ff9e98> (a simple.Data)\n",
+                "\tat java.lang.Object.wait(Object.java:502)\n",
+                "\tat simple.Data.get(Object.java:47)\n",
+                "\t- locked <0x00000000d6ff9e98> (a simple.Data)\n",
+                "\n",
+            )
+        );
+    }
+
+    #[test]
+    fn state_is_inferred_from_top_frame() {
+        let thread = |frames| JstackThread {
+            name: "t".to_string(),
+            thread_serial_number: 1,
+            thread_object_id: None,
+            frames,
+            waiting_on: None,
+            locked: vec![],
+        };
+        assert_eq!(
+            thread(vec![frame("java.lang.Thread", "sleep", -3)])
+                .state()
+                .1,
+            "TIMED_WAITING (sleeping)"
+        );
+        assert_eq!(
+            thread(vec![frame("sun.misc.Unsafe", "park", -3)]).state().1,
+            "WAITING (parking)"
+        );
+        assert_eq!(thread(vec![]).state().1, "RUNNABLE");
+    }
+}
@@ -0,0 +1,178 @@
+//! `--locks` — objects used as monitors, the threads referencing them from
+//! their stack frames and the threads waiting on them. The dump does not
+//! record lock ownership: a thread whose top frame is `Object.wait` is
+//! considered waiting on the monitor held by its topmost frame holding one.
+
+use std::fmt::Write;
+
+use ahash::AHashMap;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::gc_roots::RootKind;
+use crate::utils::pluralize;
+
+// A thread and the frame referencing a monitor.
+#[derive(Serialize, JsonSchema, Debug, Clone, PartialEq, Eq)]
+pub struct MonitorReference {
+    pub thread: String,
+    pub frame: String,
+}
+
+#[derive(Serialize, JsonSchema, Debug, Clone)]
+pub struct Monitor {
+    pub object_id: u64,
+    // `None` when the object is not in the dump
+    pub class_name: Option<String>,
+    pub root_kinds: Vec<RootKind>,
+    pub waiting: Vec<MonitorReference>,
+    pub referencing: Vec<MonitorReference>,
+}
+
+impl Monitor {
+    pub const fn new(object_id: u64, class_name: Option<String>) -> Self {
+        Self {
+            object_id,
+            class_name,
+            root_kinds: Vec::new(),
+            waiting: Vec::new(),
+            referencing: Vec::new(),
+        }
+    }
+
+    fn description(&self) -> String {
+        match &self.class_name {
+            Some(class_name) => format!("<0x{:x}> (a {class_name})", self.object_id),
+            None => format!("<0x{:x}> (not in the dump)", self.object_id),
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema, Clone)]
+pub struct MonitorReport {
+    pub monitors: Vec<Monitor>,
+}
+
+impl MonitorReport {
+    pub fn new(mut monitors: Vec<Monitor>) -> Self {
+        for monitor in &mut monitors {
+            monitor.root_kinds.sort_unstable();
+            monitor.root_kinds.dedup();
+        }
+        monitors.sort_unstable_by(|a, b| {
+            b.waiting
+                .len()
+                .cmp(&a.waiting.len())
+                .then(a.class_name.cmp(&b.class_name))
+                .then(a.object_id.cmp(&b.object_id))
+        });
+        Self { monitors }
+    }
+
+    // Monitors several threads are waiting on at the same time.
+    pub fn contended(&self) -> impl Iterator<Item = &Monitor> {
+        self.monitors.iter().filter(|m| m.waiting.len() > 1)
+    }
+
+    fn monitors_by_class(&self) -> Vec<(&str, u64)> {
+        let mut by_class: AHashMap<&str, u64> = AHashMap::new();
+        for monitor in &self.monitors {
+            let class_name = monitor.class_name.as_deref().unwrap_or("<not in the dump>");
+            *by_class.entry(class_name).or_default() += 1;
+        }
+        let mut by_class: Vec<_> = by_class.into_iter().collect();
+        by_class.sort_unstable_by(|(a_name, a), (b_name, b)| b.cmp(a).then(a_name.cmp(b_name)));
+        by_class
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "\nFound {}:",
+            pluralize(self.monitors.len(), "monitor", "monitors")
+        );
+        if self.monitors.is_empty() {
+            return out;
+        }
+
+        let _ = writeln!(out, "\n{:>10}  Class", "Monitors");
+        for (class_name, count) in self.monitors_by_class() {
+            let _ = writeln!(out, "{count:>10}  {class_name}");
+        }
+
+        for monitor in &self.monitors {
+            let kinds: Vec<_> = monitor.root_kinds.iter().map(|k| k.label()).collect();
+            let _ = writeln!(out, "\n{} [{}]", monitor.description(), kinds.join(", "));
+            for reference in &monitor.waiting {
+                let _ = writeln!(
+                    out,
+                    "  waited on by {} at {}",
+                    reference.thread, reference.frame
+                );
+            }
+            for reference in &monitor.referencing {
+                let _ = writeln!(
+                    out,
+                    "  referenced by {} at {}",
+                    reference.thread, reference.frame
+                );
+            }
+        }
+
+        for monitor in self.contended() {
+            let threads: Vec<_> = monitor.waiting.iter().map(|r| r.thread.as_str()).collect();
+            let _ = writeln!(
+                out,
+                "\nWarning: {} threads are waiting on {}: {}",
+                threads.len(),
+                monitor.description(),
+                threads.join(", ")
+            );
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reference(thread: &str) -> MonitorReference {
+        MonitorReference {
+            thread: thread.to_string(),
+            frame: "java.lang.Object.wait (Object.java:502)".to_string(),
+        }
+    }
+
+    #[test]
+    fn contended_monitors_are_flagged_first() {
+        let mut lonely = Monitor::new(1, Some("com.example.Lock".to_string()));
+        lonely.root_kinds = vec![RootKind::MonitorUsed, RootKind::MonitorUsed];
+        lonely.waiting.push(reference("Thread 1"));
+        let mut contended = Monitor::new(2, Some("com.example.Lock".to_string()));
+        contended.root_kinds = vec![RootKind::MonitorUsed];
+        contended.waiting.push(reference("Thread 2"));
+        contended.waiting.push(reference("Thread 3"));
+        let missing = Monitor::new(3, None);
+
+        let report = MonitorReport::new(vec![lonely, missing, contended]);
+
+        assert_eq!(report.monitors[0].object_id, 2);
+        assert_eq!(report.monitors[1].root_kinds, vec![RootKind::MonitorUsed]);
+        assert_eq!(report.contended().count(), 1);
+        assert_eq!(
+            report.monitors_by_class(),
+            vec![("com.example.Lock", 2), ("<not in the dump>", 1)]
+        );
+
+        let rendered = report.render();
+        assert!(rendered.contains("Found 3 monitors:"));
+        assert!(rendered.contains("\n<0x2> (a com.example.Lock) [monitor used]\n"));
+        assert!(rendered.contains("  waited on by Thread 2 at java.lang.Object.wait"));
+        assert!(rendered.contains(
+            "Warning: 2 threads are waiting on <0x2> (a com.example.Lock): Thread 2, Thread 3"
+        ));
+        assert!(rendered.contains("<0x3> (not in the dump)"));
+    }
+}
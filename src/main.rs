@@ -1,27 +1,52 @@
+mod alloc_traces;
+mod android;
 mod args;
+mod array_distribution;
+mod collapsed;
+mod descriptor;
 mod diff;
+mod diff_rules;
+mod dumps;
 mod errors;
+mod flamegraph;
+mod gc_roots;
+mod heaps;
+mod html;
+mod jstack;
+mod locks;
+mod markdown;
 mod parser;
 mod prefetch_reader;
 mod rendered_result;
 mod result_recorder;
 mod slurp;
+mod table_export;
+mod thread_groups;
+mod thread_lifecycle;
+mod trend;
 mod utils;
+mod zero_arrays;
 
 use std::time::Instant;
 
-use rendered_result::{DumpInfo, JsonResult};
+use rendered_result::{
+    DumpInfo, JsonDiffResult, JsonResult, RenderedResult, diff_json_schema, json_schema,
+};
 
-use crate::args::{Args, DiffArgs, ParsedArgs, get_args};
+use crate::args::{Args, DiffArgs, ParsedArgs, ReportOptions, TrendArgs, get_args};
+use crate::diff::DiffTotals;
 use crate::errors::HprofSlurpError;
+use crate::parser::file_header_parser::FileHeader;
 use crate::slurp::slurp_file;
+use crate::table_export::TableFormat;
+use crate::trend::TrendReport;
 
 fn main() {
     std::process::exit(match main_result() {
         Ok(()) => 0,
         Err(err) => {
             eprintln!("error: {err}");
-            1
+            err.exit_code()
         }
     });
 }
@@ -30,13 +55,27 @@ fn main_result() -> Result<(), HprofSlurpError> {
     let now = Instant::now();
     match get_args()? {
         ParsedArgs::Analyze(args) => {
-            analyze_file(args)?;
+            analyze_file(*args)?;
             println!("File successfully processed in {:?}", now.elapsed());
         }
         ParsedArgs::Diff(diff_args) => {
             diff_files(diff_args)?;
             println!("Files successfully compared in {:?}", now.elapsed());
         }
+        ParsedArgs::Trend(trend_args) => {
+            trend_files(trend_args)?;
+            println!("Files successfully analyzed in {:?}", now.elapsed());
+        }
+        ParsedArgs::Schema { diff } => {
+            let schema = if diff {
+                diff_json_schema()
+            } else {
+                json_schema()
+            };
+            // nothing else on stdout so it can be redirected to a file
+            let schema = serde_json::to_string_pretty(&schema)?;
+            println!("{schema}");
+        }
     }
     Ok(())
 }
@@ -46,33 +85,107 @@ fn analyze_file(args: Args) -> Result<(), HprofSlurpError> {
         file_path,
         top,
         debug,
-        list_strings,
+        reports,
+        jstack_output,
+        alloc_collapsed_output,
+        flamegraph_output,
+        collapsed_output,
+        html_output,
+        markdown_output,
+        csv_output,
+        tsv_output,
+        table_columns,
         json_output,
         output_file,
     } = args;
-    let (file_header, mut rendered_result) = slurp_file(&file_path, debug, list_strings)?;
+    let (file_header, mut rendered_result) = slurp_file(&file_path, debug, reports)?;
+    let dump_info = dump_info(&file_path, file_header, &rendered_result)?;
+    if let Some(html_output) = html_output {
+        std::fs::write(&html_output, html::render(&dump_info, &rendered_result))?;
+        println!("Output HTML report file {html_output}");
+    }
+    if let Some(markdown_output) = markdown_output {
+        let markdown = markdown::render(&dump_info, &rendered_result, top);
+        std::fs::write(&markdown_output, markdown)?;
+        println!("Output Markdown report file {markdown_output}");
+    }
+    for (output, format) in [
+        (csv_output, TableFormat::Csv),
+        (tsv_output, TableFormat::Tsv),
+    ] {
+        if let Some(output) = output {
+            let table = table_export::render(&rendered_result.memory_usage, format, table_columns);
+            std::fs::write(&output, table)?;
+            println!("Output {} file {output}", format.name());
+        }
+    }
     if json_output {
-        // only dump metadata and memory usage rendered for now
-        let file_size_bytes = std::fs::metadata(&file_path)?.len();
-        let dump_info = DumpInfo::new(
-            file_path,
-            file_size_bytes,
-            file_header.format,
-            file_header.size_pointers,
-            file_header.timestamp,
-        );
-        let json_result = JsonResult::new(dump_info, &mut rendered_result.memory_usage, top);
+        let json_result = JsonResult::from_rendered(dump_info, &mut rendered_result, top);
         json_result.save_as_file(output_file.as_deref())?;
     }
+    if let (Some(jstack_output), Some(jstack)) = (jstack_output, rendered_result.jstack.take()) {
+        std::fs::write(&jstack_output, jstack)?;
+        println!("Output jstack thread dump file {jstack_output}");
+    }
+    if let (Some(alloc_collapsed_output), Some(collapsed)) = (
+        alloc_collapsed_output,
+        rendered_result.alloc_collapsed.take(),
+    ) {
+        std::fs::write(&alloc_collapsed_output, collapsed)?;
+        println!("Output collapsed allocation stacks file {alloc_collapsed_output}");
+    }
+    if let Some(flamegraph_output) = flamegraph_output {
+        let title = format!("Raw shallow heap of {file_path}");
+        let svg = flamegraph::render(&rendered_result.memory_usage, &title);
+        std::fs::write(&flamegraph_output, svg)?;
+        println!("Output flamegraph file {flamegraph_output}");
+    }
+    if let Some(collapsed_output) = collapsed_output {
+        let collapsed = collapsed::render_classes(&rendered_result.memory_usage);
+        std::fs::write(&collapsed_output, collapsed)?;
+        println!("Output collapsed stacks file {collapsed_output}");
+    }
     print!("{}", rendered_result.serialize(top));
     Ok(())
 }
 
+fn dump_info(
+    file_path: &str,
+    file_header: FileHeader,
+    rendered_result: &RenderedResult,
+) -> Result<DumpInfo, HprofSlurpError> {
+    let file_size_bytes = std::fs::metadata(file_path)?.len();
+    let dump_info = DumpInfo::new(
+        file_path.to_string(),
+        file_size_bytes,
+        file_header.format,
+        file_header.size_pointers,
+        file_header.timestamp,
+    )
+    .with_records(
+        rendered_result.heap_summary,
+        rendered_result.control_settings,
+    );
+    Ok(dump_info)
+}
+
 fn diff_files(diff_args: DiffArgs) -> Result<(), HprofSlurpError> {
-    let DiffArgs { from, to, top } = diff_args;
-    let (_, result_from) = slurp_file(&from, false, false)?;
-    let (_, result_to) = slurp_file(&to, false, false)?;
+    let DiffArgs {
+        from,
+        to,
+        top,
+        csv_output,
+        json_output,
+        output_file,
+        rules,
+    } = diff_args;
+    let (header_from, result_from) = slurp_file(&from, false, ReportOptions::default())?;
+    let (header_to, result_to) = slurp_file(&to, false, ReportOptions::default())?;
     let entries = diff::compute(&result_from.memory_usage, &result_to.memory_usage);
+    if let Some(csv_output) = csv_output {
+        std::fs::write(&csv_output, diff::render_csv(&entries))?;
+        println!("Output CSV file {csv_output}");
+    }
     print!(
         "{}",
         diff::render(
@@ -84,5 +197,57 @@ fn diff_files(diff_args: DiffArgs) -> Result<(), HprofSlurpError> {
             top
         )
     );
+    let totals = DiffTotals::new(&result_from.memory_usage, &result_to.memory_usage);
+    let rule_results = diff_rules::check(
+        &rules,
+        &result_from.memory_usage,
+        &result_to.memory_usage,
+        &totals,
+        &entries,
+    );
+    if json_output {
+        let json_result = JsonDiffResult::new(
+            dump_info(&from, header_from, &result_from)?,
+            dump_info(&to, header_to, &result_to)?,
+            totals,
+            entries,
+        );
+        json_result.save_as_file(output_file.as_deref())?;
+    }
+    if !rule_results.is_empty() {
+        print!("{}", diff_rules::render(&rule_results, top));
+        let violated = rule_results
+            .iter()
+            .filter(|result| !result.violations.is_empty())
+            .count();
+        if violated > 0 {
+            return Err(HprofSlurpError::DiffRulesViolated { violated });
+        }
+    }
+    Ok(())
+}
+
+fn trend_files(trend_args: TrendArgs) -> Result<(), HprofSlurpError> {
+    let TrendArgs {
+        files,
+        top,
+        time_unit,
+        csv_output,
+    } = trend_args;
+    let mut snapshots = Vec::with_capacity(files.len());
+    for file in files {
+        let (file_header, result) = slurp_file(&file, false, ReportOptions::default())?;
+        // `0` means the dumper did not record a capture time
+        if file_header.timestamp == 0 {
+            return Err(HprofSlurpError::MissingCaptureTime { name: file });
+        }
+        snapshots.push((file, file_header.timestamp, result.memory_usage));
+    }
+    let report = TrendReport::new(snapshots);
+    if let Some(csv_output) = csv_output {
+        std::fs::write(&csv_output, report.render_csv())?;
+        println!("Output CSV file {csv_output}");
+    }
+    print!("{}", report.render(top, time_unit));
     Ok(())
 }
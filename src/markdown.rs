@@ -0,0 +1,126 @@
+//! `--markdown` — the report as GitHub-flavoured Markdown to paste in a
+//! postmortem or a pull request: dump metadata, summary, top classes, thread
+//! groups or stacks, and warnings.
+
+use std::fmt::Write;
+
+use crate::rendered_result::{ClassAllocationStats, DumpInfo, RenderedResult};
+use crate::thread_groups::ThreadGroup;
+use crate::utils::{pluralize, pretty_bytes_size};
+
+// `|` ends a table cell, the other characters would be read as markup.
+fn escape_cell(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '|' | '\\' | '`' | '*' | '_' | '<' | '>') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn render_code_block(out: &mut String, text: &str) {
+    let _ = writeln!(out, "```text\n{}\n```\n", text.trim_matches('\n'));
+}
+
+fn render_top_classes(out: &mut String, memory_usage: &[ClassAllocationStats], top: usize) {
+    let mut rows: Vec<_> = memory_usage.iter().collect();
+    rows.sort_by_key(|stats| std::cmp::Reverse(stats.allocation_size_bytes));
+    let _ = writeln!(out, "## Top {top} raw shallow heap classes\n");
+    let _ = writeln!(
+        out,
+        "| Class | Instances | Largest instance | Total size |\n| --- | ---: | ---: | ---: |"
+    );
+    for stats in rows.into_iter().take(top) {
+        let _ = writeln!(
+            out,
+            "| {} | {} | {} | {} |",
+            escape_cell(&stats.class_name),
+            stats.instance_count,
+            pretty_bytes_size(stats.largest_allocation_bytes),
+            pretty_bytes_size(stats.allocation_size_bytes)
+        );
+    }
+    out.push('\n');
+}
+
+fn render_thread_groups(out: &mut String, groups: &[ThreadGroup]) {
+    let _ = writeln!(out, "## Thread groups\n");
+    for group in groups {
+        let _ = writeln!(
+            out,
+            "**{}**: {}\n",
+            pluralize(group.threads.len(), "thread", "threads"),
+            escape_cell(&group.threads.join(", "))
+        );
+        let frames: Vec<_> = group.frames.iter().map(|f| format!("at {f}")).collect();
+        render_code_block(out, &frames.join("\n"));
+    }
+}
+
+pub fn render(dump: &DumpInfo, result: &RenderedResult, top: usize) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# hprof-slurp report\n");
+    let _ = writeln!(out, "| | |\n| --- | --- |");
+    for (label, value) in dump.fields() {
+        let _ = writeln!(out, "| {label} | {} |", escape_cell(&value));
+    }
+    out.push('\n');
+
+    if let Some(warnings) = &result.warnings {
+        let _ = writeln!(out, "> [!WARNING]");
+        for line in warnings.trim_matches('\n').lines() {
+            let _ = writeln!(out, "> {line}");
+        }
+        out.push('\n');
+    }
+
+    let _ = writeln!(out, "## Summary\n");
+    render_code_block(&mut out, &result.summary);
+
+    render_top_classes(&mut out, &result.memory_usage, top);
+
+    match &result.thread_groups {
+        Some(groups) => render_thread_groups(&mut out, groups),
+        None => {
+            let _ = writeln!(out, "## Threads\n");
+            render_code_block(&mut out, &result.thread_info);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn top_classes_are_a_table() {
+        let memory_usage = vec![
+            ClassAllocationStats::new("java.lang.String".to_string(), 3, 24, 72),
+            ClassAllocationStats::new("Outer|Inner".to_string(), 1, 96, 96),
+            ClassAllocationStats::new("byte[]".to_string(), 1, 8, 8),
+        ];
+        let mut out = String::new();
+        render_top_classes(&mut out, &memory_usage, 2);
+        assert_eq!(
+            out,
+            "## Top 2 raw shallow heap classes\n\n| Class | Instances | Largest instance | Total size |\n| --- | ---: | ---: | ---: |\n| Outer\\|Inner | 1 | 96.00bytes | 96.00bytes |\n| java.lang.String | 3 | 24.00bytes | 72.00bytes |\n\n"
+        );
+    }
+
+    #[test]
+    fn thread_groups_list_their_frames() {
+        let groups = vec![ThreadGroup {
+            threads: vec!["worker_1".to_string(), "worker_2".to_string()],
+            frames: vec!["java.lang.Object.wait() (Object.java:502)".to_string()],
+        }];
+        let mut out = String::new();
+        render_thread_groups(&mut out, &groups);
+        assert_eq!(
+            out,
+            "## Thread groups\n\n**2 threads**: worker\\_1, worker\\_2\n\n```text\nat java.lang.Object.wait() (Object.java:502)\n```\n\n"
+        );
+    }
+}
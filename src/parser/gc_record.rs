@@ -1,6 +1,6 @@
 #![allow(dead_code)]
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum FieldType {
     Object = 2,
     Bool = 4,
@@ -30,6 +30,17 @@ impl FieldType {
     }
 }
 
+// Size of an element of a primitive array.
+pub fn primitive_byte_size(field_type: FieldType) -> u64 {
+    match field_type {
+        FieldType::Byte | FieldType::Bool => 1,
+        FieldType::Char | FieldType::Short => 2,
+        FieldType::Float | FieldType::Int => 4,
+        FieldType::Double | FieldType::Long => 8,
+        FieldType::Object => panic!("object type in primitive array"),
+    }
+}
+
 #[derive(Debug)]
 pub struct ConstFieldInfo {
     pub const_pool_idx: u16,
@@ -69,6 +80,7 @@ pub enum ArrayValue {
 }
 
 #[derive(Debug)]
+#[allow(clippy::box_collection)] // Box<Vec> is intentional: keeps GcRecord at 40 bytes instead of 56
 pub enum GcRecord {
     RootUnknown {
         object_id: u64,
@@ -153,11 +165,14 @@ pub enum GcRecord {
         number_of_elements: u32,
         element_type: FieldType,
     },
+    /// `field_values` holds the raw instance field values, only kept when the
+    /// parser is asked to (this class fields first, then its super classes).
     InstanceDump {
         object_id: u64,
         stack_trace_serial_number: u32,
         class_object_id: u64,
         data_size: u32,
+        field_values: Option<Box<Vec<u8>>>,
     },
     ObjectArrayDump {
         object_id: u64,
@@ -165,11 +180,14 @@ pub enum GcRecord {
         number_of_elements: u32,
         array_class_id: u64,
     },
+    /// `zero_elements` is only computed when the parser is asked to inspect
+    /// array bodies (see `--zero-arrays`), it is `0` otherwise.
     PrimitiveArrayDump {
         object_id: u64,
         stack_trace_serial_number: u32,
         number_of_elements: u32,
         element_type: FieldType,
+        zero_elements: u32,
     },
     ClassDump(Box<ClassDumpFields>), // rare enough to be boxed to avoid large variant cost
 }
@@ -179,6 +197,8 @@ pub struct ClassDumpFields {
     pub class_object_id: u64,
     pub stack_trace_serial_number: u32,
     pub super_class_object_id: u64,
+    // `0` for the bootstrap class loader
+    pub class_loader_object_id: u64,
     pub instance_size: u32,
     pub const_fields: Vec<(ConstFieldInfo, FieldValue)>,
     pub static_fields: Vec<(FieldInfo, FieldValue)>,
@@ -199,10 +219,16 @@ impl ClassDumpFields {
             class_object_id,
             stack_trace_serial_number,
             super_class_object_id,
+            class_loader_object_id: 0,
             instance_size,
             const_fields,
             static_fields,
             instance_fields,
         }
     }
+
+    pub const fn with_class_loader(mut self, class_loader_object_id: u64) -> Self {
+        self.class_loader_object_id = class_loader_object_id;
+        self
+    }
 }
@@ -1,5 +1,6 @@
 pub mod file_header_parser;
 pub mod gc_record;
+mod modified_utf8;
 mod primitive_parsers;
 pub mod record;
 pub mod record_parser;
@@ -0,0 +1,135 @@
+//! Java's modified UTF-8 (JVMS §4.4.7) used by the HPROF string records: NUL
+//! is encoded on two bytes and supplementary characters as two encoded UTF-16
+//! surrogates (CESU-8) instead of one four bytes sequence.
+
+const REPLACEMENT: char = char::REPLACEMENT_CHARACTER;
+
+fn is_continuation(byte: u8) -> bool {
+    byte & 0b1100_0000 == 0b1000_0000
+}
+
+// The UTF-16 code unit of the three bytes sequence at the start of `bytes`.
+fn three_bytes_unit(bytes: &[u8]) -> Option<u16> {
+    match *bytes {
+        [a, b, c, ..]
+            if a & 0b1111_0000 == 0b1110_0000 && is_continuation(b) && is_continuation(c) =>
+        {
+            Some(
+                (u16::from(a & 0b0000_1111) << 12)
+                    | (u16::from(b & 0b0011_1111) << 6)
+                    | u16::from(c & 0b0011_1111),
+            )
+        }
+        _ => None,
+    }
+}
+
+/// Decodes `bytes`, returns the string with the number of undecodable
+/// sequences, each replaced by U+FFFD.
+///
+/// Standard four bytes UTF-8 sequences are not valid modified UTF-8 but are
+/// accepted as some dumpers write them.
+pub fn decode_modified_utf8(bytes: &[u8]) -> (Box<str>, u32) {
+    // class names and most strings are plain ASCII
+    if bytes.is_ascii() {
+        let ascii = std::str::from_utf8(bytes).expect("ASCII is valid UTF-8");
+        return (ascii.into(), 0);
+    }
+
+    let mut decoded = String::with_capacity(bytes.len());
+    let mut undecodable = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        let byte = bytes[i];
+        match byte {
+            0x00..=0x7f => {
+                decoded.push(char::from(byte));
+                i += 1;
+            }
+            // two bytes, including the `C0 80` NUL
+            0xc0..=0xdf if bytes.get(i + 1).is_some_and(|b| is_continuation(*b)) => {
+                let code =
+                    (u32::from(byte & 0b0001_1111) << 6) | u32::from(bytes[i + 1] & 0b0011_1111);
+                decoded.push(char::from_u32(code).unwrap_or(REPLACEMENT));
+                i += 2;
+            }
+            0xe0..=0xef if three_bytes_unit(&bytes[i..]).is_some() => {
+                let unit = three_bytes_unit(&bytes[i..]).unwrap_or_default();
+                let low = three_bytes_unit(&bytes[i + 3..]);
+                match (unit, low) {
+                    (0xd800..=0xdbff, Some(low @ 0xdc00..=0xdfff)) => {
+                        let code = 0x1_0000
+                            + ((u32::from(unit) - 0xd800) << 10)
+                            + (u32::from(low) - 0xdc00);
+                        decoded.push(char::from_u32(code).unwrap_or(REPLACEMENT));
+                        i += 6;
+                    }
+                    // lone surrogate
+                    (0xd800..=0xdfff, _) => {
+                        decoded.push(REPLACEMENT);
+                        undecodable += 1;
+                        i += 3;
+                    }
+                    (unit, _) => {
+                        decoded.push(char::from_u32(u32::from(unit)).unwrap_or(REPLACEMENT));
+                        i += 3;
+                    }
+                }
+            }
+            0xf0..=0xf7 => match bytes
+                .get(i..i + 4)
+                .and_then(|s| std::str::from_utf8(s).ok())
+            {
+                Some(s) => {
+                    decoded.push_str(s);
+                    i += 4;
+                }
+                None => {
+                    decoded.push(REPLACEMENT);
+                    undecodable += 1;
+                    i += 1;
+                }
+            },
+            _ => {
+                decoded.push(REPLACEMENT);
+                undecodable += 1;
+                i += 1;
+            }
+        }
+    }
+    (decoded.into_boxed_str(), undecodable)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_encoded_nul_and_surrogate_pairs() {
+        assert_eq!(
+            decode_modified_utf8(b"java/lang/String"),
+            ("java/lang/String".into(), 0)
+        );
+        assert_eq!(decode_modified_utf8(b"a\xc0\x80b"), ("a\0b".into(), 0));
+        assert_eq!(decode_modified_utf8("été€".as_bytes()), ("été€".into(), 0));
+        // U+1F600 as the surrogates D83D DE00
+        assert_eq!(
+            decode_modified_utf8(b"\xed\xa0\xbd\xed\xb8\x80!"),
+            ("\u{1f600}!".into(), 0)
+        );
+        // standard UTF-8 is tolerated
+        assert_eq!(
+            decode_modified_utf8("\u{1f600}".as_bytes()),
+            ("\u{1f600}".into(), 0)
+        );
+    }
+
+    #[test]
+    fn counts_undecodable_sequences() {
+        // lone high surrogate, stray continuation byte, truncated sequence
+        assert_eq!(
+            decode_modified_utf8(b"\xed\xa0\xbdx\x80y\xe2\x82"),
+            ("\u{fffd}x\u{fffd}y\u{fffd}\u{fffd}".into(), 4)
+        );
+    }
+}
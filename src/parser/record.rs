@@ -57,6 +57,8 @@ pub enum Record {
     Utf8String {
         id: u64,
         str: Box<str>,
+        // replaced by U+FFFD in `str`
+        undecodable_sequences: u32,
     },
     LoadClass(LoadClassData),
     UnloadClass {
@@ -75,6 +77,8 @@ pub enum Record {
         allocation_sites: Box<Vec<AllocationSite>>,
     },
     StartThread {
+        // microseconds since the header timestamp
+        timestamp: u32,
         thread_serial_number: u32,
         thread_object_id: u64,
         stack_trace_serial_number: u32,
@@ -83,6 +87,7 @@ pub enum Record {
         thread_group_parent_name_id: u64,
     },
     EndThread {
+        timestamp: u32,
         thread_serial_number: u32,
     },
     HeapSummary {
@@ -93,6 +98,11 @@ pub enum Record {
     },
     HeapDumpStart {
         length: u32,
+        // microseconds since the file header timestamp
+        timestamp: u32,
+        // a `HEAP DUMP SEGMENT` continuing the current dump until `HeapDumpEnd`,
+        // otherwise a whole `HEAP DUMP`
+        segment: bool,
     },
     HeapDumpEnd {
         length: u32,
@@ -1,6 +1,8 @@
 use crate::parser::gc_record::{
     ArrayValue, ClassDumpFields, ConstFieldInfo, FieldInfo, FieldType, FieldValue, GcRecord,
+    primitive_byte_size,
 };
+use crate::parser::modified_utf8::decode_modified_utf8;
 use crate::parser::primitive_parsers::{
     parse_f32, parse_f64, parse_i8, parse_i16, parse_i32, parse_i64, parse_u8, parse_u16,
     parse_u32, parse_u64,
@@ -68,14 +70,23 @@ const TAG_GC_HEAP_DUMP_INFO: u8 = 0xFE;
 pub struct HprofRecordParser {
     debug_mode: bool,
     id_size: u32,
+    count_zero_elements: bool,
+    keep_field_values: bool,
     heap_dump_remaining_len: u32,
 }
 
 impl HprofRecordParser {
-    pub const fn new(debug_mode: bool, id_size: u32) -> Self {
+    pub const fn new(
+        debug_mode: bool,
+        id_size: u32,
+        count_zero_elements: bool,
+        keep_field_values: bool,
+    ) -> Self {
         Self {
             debug_mode,
             id_size,
+            count_zero_elements,
+            keep_field_values,
             heap_dump_remaining_len: 0,
         }
     }
@@ -106,7 +117,11 @@ impl HprofRecordParser {
                             map(parse_header_record, |hr| {
                                 // record expected GC segments length
                                 self.heap_dump_remaining_len = hr.length;
-                                HeapDumpStart { length: hr.length }
+                                HeapDumpStart {
+                                    length: hr.length,
+                                    timestamp: hr.timestamp,
+                                    segment: tag == TAG_HEAP_DUMP_SEGMENT,
+                                }
                             })
                             .parse(r1)
                         }
@@ -115,13 +130,17 @@ impl HprofRecordParser {
                 })
             } else {
                 // GC record mode
-                parse_gc_record(i, id_size).map(|(r1, gc_sub)| {
-                    let gc_sub_len = i.len() - r1.len();
-                    self.heap_dump_remaining_len = self
-                        .heap_dump_remaining_len
-                        .saturating_sub(gc_sub_len as u32);
-                    (r1, GcSegment(gc_sub))
-                })
+                let (count_zero_elements, keep_field_values) =
+                    (self.count_zero_elements, self.keep_field_values);
+                parse_gc_record(i, id_size, count_zero_elements, keep_field_values).map(
+                    |(r1, gc_sub)| {
+                        let gc_sub_len = i.len() - r1.len();
+                        self.heap_dump_remaining_len = self
+                            .heap_dump_remaining_len
+                            .saturating_sub(gc_sub_len as u32);
+                        (r1, GcSegment(gc_sub))
+                    },
+                )
             }
         }
     }
@@ -186,7 +205,12 @@ where
     }
 }
 
-fn parse_gc_record(i: &[u8], id_size: u32) -> IResult<&[u8], GcRecord> {
+fn parse_gc_record(
+    i: &[u8],
+    id_size: u32,
+    count_zero_elements: bool,
+    keep_field_values: bool,
+) -> IResult<&[u8], GcRecord> {
     let (r1, tag) = parse_u8(i)?;
     match tag {
         TAG_GC_ROOT_UNKNOWN => parse_gc_root_unknown(r1, id_size),
@@ -199,9 +223,9 @@ fn parse_gc_record(i: &[u8], id_size: u32) -> IResult<&[u8], GcRecord> {
         TAG_GC_ROOT_MONITOR_USED => parse_gc_root_monitor_used(r1, id_size),
         TAG_GC_ROOT_THREAD_OBJ => parse_gc_root_thread_object(r1, id_size),
         TAG_GC_CLASS_DUMP => parse_gc_class_dump(r1, id_size),
-        TAG_GC_INSTANCE_DUMP => parse_gc_instance_dump(r1, id_size),
+        TAG_GC_INSTANCE_DUMP => parse_gc_instance_dump(r1, id_size, keep_field_values),
         TAG_GC_OBJ_ARRAY_DUMP => parse_gc_object_array_dump(r1, id_size),
-        TAG_GC_PRIM_ARRAY_DUMP => parse_gc_primitive_array_dump(r1, id_size),
+        TAG_GC_PRIM_ARRAY_DUMP => parse_gc_primitive_array_dump(r1, id_size, count_zero_elements),
         // Android HPROF 1.0.3 extensions (am dumpheap on modern ART).
         TAG_GC_ROOT_INTERNED_STRING => parse_gc_root_interned_string(r1, id_size),
         TAG_GC_ROOT_FINALIZING => parse_gc_root_finalizing(r1, id_size),
@@ -380,7 +404,10 @@ fn parse_gc_heap_dump_info(i: &[u8], id_size: u32) -> IResult<&[u8], GcRecord> {
     .parse(i)
 }
 
-fn parse_field_value(ty: FieldType, id_size: u32) -> impl Fn(&[u8]) -> IResult<&[u8], FieldValue> {
+pub fn parse_field_value(
+    ty: FieldType,
+    id_size: u32,
+) -> impl Fn(&[u8]) -> IResult<&[u8], FieldValue> {
     move |i| match ty {
         FieldType::Object => map(id(id_size), FieldValue::Object).parse(i),
         FieldType::Bool => map(parse_u8, |bu8| FieldValue::Bool(bu8 != 0)).parse(i),
@@ -509,7 +536,7 @@ fn parse_gc_class_dump(i: &[u8], id_size: u32) -> IResult<&[u8], GcRecord> {
             class_object_id,
             stack_trace_serial_number,
             super_class_object_id,
-            _class_loader_object_id,
+            class_loader_object_id,
             _signers_object_id,
             _protection_domain_object_id,
             _reserved_1,
@@ -559,7 +586,8 @@ fn parse_gc_class_dump(i: &[u8], id_size: u32) -> IResult<&[u8], GcRecord> {
                             const_fields,
                             static_fields,
                             instance_fields,
-                        );
+                        )
+                        .with_class_loader(class_loader_object_id);
                         let gcd = ClassDump(Box::new(class_dump_fields));
                         (r6, gcd)
                     })
@@ -569,22 +597,31 @@ fn parse_gc_class_dump(i: &[u8], id_size: u32) -> IResult<&[u8], GcRecord> {
     })
 }
 
-fn parse_gc_instance_dump(i: &[u8], id_size: u32) -> IResult<&[u8], GcRecord> {
+fn parse_gc_instance_dump(
+    i: &[u8],
+    id_size: u32,
+    keep_field_values: bool,
+) -> IResult<&[u8], GcRecord> {
     flat_map(
         (id(id_size), parse_u32, id(id_size), parse_u32),
         |(object_id, stack_trace_serial_number, class_object_id, data_size)| {
-            map(bytes::streaming::take(data_size), move |_bytes_segment| {
-                // Important: The actual content of the instance cannot be analyzed at this point because we miss the class information!
-                // Given that instances are found before the class info in the dump file, it would require two passes on the
-                // dump file with the additional storage of intermediary results on the disk to fully analyze the instances.
-                // hprof-slurp performs a single pass and makes no assumptions on the memory or storage available.
-                InstanceDump {
-                    object_id,
-                    stack_trace_serial_number,
-                    class_object_id,
-                    data_size,
-                }
-            })
+            map(
+                bytes::streaming::take(data_size),
+                move |bytes_segment: &[u8]| {
+                    // Important: The actual content of the instance cannot be analyzed at this point because we miss the class information!
+                    // Given that instances are found before the class info in the dump file, it would require two passes on the
+                    // dump file with the additional storage of intermediary results on the disk to fully analyze the instances.
+                    // hprof-slurp performs a single pass and makes no assumptions on the memory or storage available:
+                    // the raw values are only handed over to the recorder on demand.
+                    InstanceDump {
+                        object_id,
+                        stack_trace_serial_number,
+                        class_object_id,
+                        data_size,
+                        field_values: keep_field_values.then(|| Box::new(bytes_segment.to_vec())),
+                    }
+                },
+            )
         },
     )
     .parse(i)
@@ -612,19 +649,28 @@ fn parse_gc_object_array_dump(i: &[u8], id_size: u32) -> IResult<&[u8], GcRecord
     .parse(i)
 }
 
-fn parse_gc_primitive_array_dump(i: &[u8], id_size: u32) -> IResult<&[u8], GcRecord> {
+fn parse_gc_primitive_array_dump(
+    i: &[u8],
+    id_size: u32,
+    count_zero_elements: bool,
+) -> IResult<&[u8], GcRecord> {
     flat_map(
         (id(id_size), parse_u32, parse_u32, parse_field_type),
-        |(object_id, stack_trace_serial_number, number_of_elements, element_type)| {
-            // Do not parse the array of primitives as it is not needed for any analyses so far.
-            // see `parse_array_value(element_type, number_of_elements)`
+        move |(object_id, stack_trace_serial_number, number_of_elements, element_type)| {
+            // The values are not decoded, only scanned for zero elements with
+            // `--zero-arrays`. see `parse_array_value(element_type, number_of_elements)`
             map(
                 skip_array_value(element_type, number_of_elements),
-                move |_data_array_elements| PrimitiveArrayDump {
+                move |data_array_elements| PrimitiveArrayDump {
                     object_id,
                     stack_trace_serial_number,
                     number_of_elements,
                     element_type,
+                    zero_elements: if count_zero_elements {
+                        zero_elements(element_type, data_array_elements)
+                    } else {
+                        0
+                    },
                 },
             )
         },
@@ -632,6 +678,17 @@ fn parse_gc_primitive_array_dump(i: &[u8], id_size: u32) -> IResult<&[u8], GcRec
     .parse(i)
 }
 
+// Number of elements made only of zero bytes in a raw array body.
+fn zero_elements(element_type: FieldType, data: &[u8]) -> u32 {
+    let element_size =
+        usize::try_from(primitive_byte_size(element_type)).expect("element size fits in usize");
+    let zeros = data
+        .chunks_exact(element_size)
+        .filter(|element| element.iter().all(|b| *b == 0))
+        .count();
+    u32::try_from(zeros).expect("element count fits in u32")
+}
+
 fn parse_header_record(i: &[u8]) -> IResult<&[u8], RecordHeader> {
     map((parse_u32, parse_u32), |(timestamp, length)| RecordHeader {
         timestamp,
@@ -648,8 +705,12 @@ fn parse_utf8_string(i: &[u8], id_size: u32) -> IResult<&[u8], Record> {
                 bytes::streaming::take(header_record.length.saturating_sub(id_size)),
             ),
             |(id, b)| {
-                let str = String::from_utf8_lossy(b).into();
-                Utf8String { id, str }
+                let (str, undecodable_sequences) = decode_modified_utf8(b);
+                Utf8String {
+                    id,
+                    str,
+                    undecodable_sequences,
+                }
             },
         )
     })
@@ -740,33 +801,33 @@ fn parse_stack_trace(i: &[u8], id_size: u32) -> IResult<&[u8], Record> {
 }
 
 fn parse_start_thread(i: &[u8], id_size: u32) -> IResult<&[u8], Record> {
-    preceded(
-        parse_header_record,
-        map(
-            (
-                parse_u32,
-                id(id_size),
-                parse_u32,
-                id(id_size),
-                id(id_size),
-                id(id_size),
-            ),
-            |(
-                thread_serial_number,
-                thread_object_id,
-                stack_trace_serial_number,
-                thread_name_id,
-                thread_group_name_id,
-                thread_group_parent_name_id,
-            )| StartThread {
-                thread_serial_number,
-                thread_object_id,
-                stack_trace_serial_number,
-                thread_name_id,
-                thread_group_name_id,
-                thread_group_parent_name_id,
-            },
+    map(
+        (
+            parse_header_record,
+            parse_u32,
+            id(id_size),
+            parse_u32,
+            id(id_size),
+            id(id_size),
+            id(id_size),
         ),
+        |(
+            header_record,
+            thread_serial_number,
+            thread_object_id,
+            stack_trace_serial_number,
+            thread_name_id,
+            thread_group_name_id,
+            thread_group_parent_name_id,
+        )| StartThread {
+            timestamp: header_record.timestamp,
+            thread_serial_number,
+            thread_object_id,
+            stack_trace_serial_number,
+            thread_name_id,
+            thread_group_name_id,
+            thread_group_parent_name_id,
+        },
     )
     .parse(i)
 }
@@ -793,11 +854,12 @@ fn parse_heap_summary(i: &[u8]) -> IResult<&[u8], Record> {
 }
 
 fn parse_end_thread(i: &[u8]) -> IResult<&[u8], Record> {
-    preceded(
-        parse_header_record,
-        map(parse_u32, |thread_serial_number| EndThread {
+    map(
+        (parse_header_record, parse_u32),
+        |(header_record, thread_serial_number)| EndThread {
+            timestamp: header_record.timestamp,
             thread_serial_number,
-        }),
+        },
     )
     .parse(i)
 }
@@ -914,6 +976,13 @@ fn parse_cpu_samples(i: &[u8]) -> IResult<&[u8], Record> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn records_keep_their_boxed_size() {
+        // the `Box<Vec>` payloads keep the records small, see the `clippy::box_collection` allows
+        assert_eq!(std::mem::size_of::<GcRecord>(), 40);
+        assert_eq!(std::mem::size_of::<Record>(), 48);
+    }
+
     #[test]
     fn parse_id_respects_32_bit_header_size() {
         let input = [0x12, 0x34, 0x56, 0x78, 0xaa];
@@ -947,7 +1016,7 @@ mod tests {
 
         assert!(rest.is_empty());
         match record {
-            Utf8String { id, str } => {
+            Utf8String { id, str, .. } => {
                 assert_eq!(id, 42);
                 assert_eq!(&*str, "abc");
             }
@@ -981,6 +1050,46 @@ mod tests {
         }
     }
 
+    #[test]
+    fn primitive_array_zero_elements_counted_only_on_demand() {
+        let mut buf = vec![TAG_GC_PRIM_ARRAY_DUMP];
+        buf.extend_from_slice(&1u32.to_be_bytes()); // object_id
+        buf.extend_from_slice(&0u32.to_be_bytes()); // stack_trace_serial
+        buf.extend_from_slice(&3u32.to_be_bytes()); // number_of_elements
+        buf.push(9); // element_type Short
+        buf.extend_from_slice(&[0x00, 0x00, 0x01, 0x00, 0x00, 0x00]);
+
+        for (count_zero_elements, expected) in [(true, 2), (false, 0)] {
+            let (rest, gcd) = parse_gc_record(&buf, 4, count_zero_elements, false).unwrap();
+            assert!(rest.is_empty());
+            match gcd {
+                PrimitiveArrayDump { zero_elements, .. } => assert_eq!(zero_elements, expected),
+                other => panic!("expected PrimitiveArrayDump, got {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn instance_field_values_kept_only_on_demand() {
+        let mut buf = vec![TAG_GC_INSTANCE_DUMP];
+        buf.extend_from_slice(&1u32.to_be_bytes()); // object_id
+        buf.extend_from_slice(&0u32.to_be_bytes()); // stack_trace_serial
+        buf.extend_from_slice(&2u32.to_be_bytes()); // class_object_id
+        buf.extend_from_slice(&2u32.to_be_bytes()); // data_size
+        buf.extend_from_slice(&[0xca, 0xfe]);
+
+        for (keep_field_values, expected) in [(true, Some(vec![0xca, 0xfe])), (false, None)] {
+            let (rest, gcd) = parse_gc_record(&buf, 4, false, keep_field_values).unwrap();
+            assert!(rest.is_empty());
+            match gcd {
+                InstanceDump { field_values, .. } => {
+                    assert_eq!(field_values.map(|values| *values), expected);
+                }
+                other => panic!("expected InstanceDump, got {other:?}"),
+            }
+        }
+    }
+
     // ---- Android HPROF 1.0.3 extension parsers ----
     // A 32-bit Android dump panicked with "unhandled gc record tag 141"
     // (0x8D = TAG_GC_ROOT_VM_INTERNAL). These cover the full extension set.
@@ -989,7 +1098,7 @@ mod tests {
         let mut buf = Vec::with_capacity(payload.len() + 1);
         buf.push(tag);
         buf.extend_from_slice(payload);
-        let (rest, gcd) = parse_gc_record(&buf, id_size).unwrap();
+        let (rest, gcd) = parse_gc_record(&buf, id_size, false, false).unwrap();
         assert!(rest.is_empty(), "parser left {} bytes unread", rest.len());
         gcd
     }
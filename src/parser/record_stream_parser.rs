@@ -24,11 +24,14 @@ impl HprofRecordStreamParser {
     pub const fn new(
         debug_mode: bool,
         id_size: u32,
+        count_zero_elements: bool,
+        keep_field_values: bool,
         file_len: usize,
         processed_len: usize,
         initial_loop_buffer: Vec<u8>,
     ) -> Self {
-        let parser = HprofRecordParser::new(debug_mode, id_size);
+        let parser =
+            HprofRecordParser::new(debug_mode, id_size, count_zero_elements, keep_field_values);
         Self {
             parser,
             debug_mode,
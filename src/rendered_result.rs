@@ -1,19 +1,34 @@
 use std::time::{SystemTime, UNIX_EPOCH};
 use std::{fmt::Write, fs::File, io::BufWriter};
 
+use schemars::JsonSchema;
 use serde::Serialize;
 
 use crate::{
+    alloc_traces::AllocationTraceReport,
+    android::AndroidReport,
+    array_distribution::ArrayDistribution,
+    diff::{DiffEntry, DiffTotals},
+    dumps::DumpReport,
     errors::HprofSlurpError,
-    utils::{pretty_bytes_size, pretty_timestamp_utc},
+    gc_roots::GcRootReport,
+    heaps::HeapUsage,
+    locks::MonitorReport,
+    thread_groups::ThreadGroup,
+    thread_lifecycle::ThreadLifecycleReport,
+    utils::{pluralize, pretty_bytes_size, pretty_timestamp_utc},
+    zero_arrays::ZeroArrayReport,
 };
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, JsonSchema, Clone)]
 pub struct ClassAllocationStats {
     pub class_name: String,
     pub instance_count: u64,
     pub largest_allocation_bytes: u64,
     pub allocation_size_bytes: u64,
+    // only with `--classloader-column`
+    #[serde(skip)]
+    pub class_loader: Option<String>,
 }
 
 impl ClassAllocationStats {
@@ -28,20 +43,192 @@ impl ClassAllocationStats {
             instance_count,
             largest_allocation_bytes,
             allocation_size_bytes,
+            class_loader: None,
         }
     }
+
+    pub fn with_class_loader(mut self, class_loader: Option<String>) -> Self {
+        self.class_loader = class_loader;
+        self
+    }
+}
+
+// Bump on any breaking change of the JSON output structures, then regenerate
+// the published schemas with `hprof-slurp schema > schema/hprof-slurp.schema.json`
+// and `hprof-slurp schema --diff > schema/hprof-slurp-diff.schema.json`.
+const JSON_SCHEMA_VERSION: u32 = 2;
+
+fn with_schema_version(schema: schemars::Schema) -> serde_json::Value {
+    let mut schema = serde_json::to_value(schema).expect("schema should serialize");
+    schema["properties"]["schema_version"]["const"] = JSON_SCHEMA_VERSION.into();
+    schema
+}
+
+// JSON Schema of the `--json` output, printed by `hprof-slurp schema`.
+pub fn json_schema() -> serde_json::Value {
+    with_schema_version(schemars::schema_for!(JsonResult))
+}
+
+// JSON Schema of the `diff --json` output, printed by `hprof-slurp schema --diff`.
+pub fn diff_json_schema() -> serde_json::Value {
+    with_schema_version(schemars::schema_for!(JsonDiffResult))
+}
+
+// Writes `value` to `output_path`, by default `hprof-slurp-<timestamp>.json`.
+fn save_json_file<T: Serialize>(
+    value: &T,
+    output_path: Option<&str>,
+) -> Result<(), HprofSlurpError> {
+    let file_path = output_path.map_or_else(
+        || {
+            let millis = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("system clock should be set after 1970")
+                .as_millis();
+            format!("hprof-slurp-{millis}.json")
+        },
+        str::to_string,
+    );
+    let file = File::create(&file_path)?;
+    let writer = BufWriter::new(file);
+    // Serialize the struct directly to the file via the writer
+    serde_json::to_writer(writer, value)?;
+    println!("Output JSON result file {file_path}");
+    Ok(())
+}
+
+// Number of records of each type in the file, see the file content summary.
+#[derive(Serialize, JsonSchema, Clone, Debug, Default, PartialEq, Eq)]
+pub struct RecordCounts {
+    pub utf8_strings: u64,
+    pub classes_loaded: u64,
+    pub classes_unloaded: u32,
+    pub stack_traces: u32,
+    pub stack_frames: u32,
+    pub start_threads: u32,
+    pub allocation_sites: u32,
+    pub end_threads: u32,
+    pub control_settings: u32,
+    pub cpu_samples: u32,
+    pub heap_summaries: u32,
+    pub heap_dumps: u32,
+    pub heap_dump_sub_records: u32,
+    pub gc_roots: GcRootCounts,
+    pub primitive_array_dumps: u32,
+    pub object_array_dumps: u32,
+    pub class_dumps: u32,
+    pub instance_dumps: u32,
+}
+
+#[derive(Serialize, JsonSchema, Clone, Debug, Default, PartialEq, Eq)]
+pub struct GcRootCounts {
+    pub unknown: u32,
+    pub thread_object: u32,
+    pub jni_global: u32,
+    pub jni_local: u32,
+    pub java_frame: u32,
+    pub native_stack: u32,
+    pub sticky_class: u32,
+    pub thread_block: u32,
+    pub monitor_used: u32,
+    // Android HPROF 1.0.3 extensions
+    pub interned_string: u32,
+    pub finalizing: u32,
+    pub debugger: u32,
+    pub reference_cleanup: u32,
+    pub vm_internal: u32,
+    pub jni_monitor: u32,
+    pub unreachable: u32,
+}
+
+#[derive(Serialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+pub struct ThreadStack {
+    pub name: String,
+    pub thread_serial_number: u32,
+    pub stack_trace_serial_number: u32,
+    // rendered stack frames, the top one first
+    pub frames: Vec<String>,
+}
+
+#[derive(Serialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+pub struct DuplicatedString {
+    pub value: String,
+    pub count: u64,
 }
 
-// Bump on any breaking change of the JSON output structure.
-const JSON_SCHEMA_VERSION: u32 = 1;
+#[derive(Serialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+pub struct DuplicatedStrings {
+    // strings equal to a previous one
+    pub duplicated_count: u64,
+    pub string_count: u64,
+    // the most repeated values first
+    pub most_duplicated: Vec<DuplicatedString>,
+}
+
+impl DuplicatedStrings {
+    pub fn render(&self) -> String {
+        format!(
+            "\nFound {} out of {}\n",
+            pluralize(
+                self.duplicated_count,
+                "duplicated string",
+                "duplicated strings"
+            ),
+            pluralize(self.string_count, "string", "strings")
+        )
+    }
+}
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema, Clone, Debug, Default, PartialEq, Eq)]
+pub struct WarningsInfo {
+    // ids of the classes referenced by objects but absent from the dump
+    pub missing_class_ids: Vec<u64>,
+    pub undecodable_utf8_sequences: u64,
+}
+
+#[derive(Serialize, JsonSchema)]
 struct ToolInfo {
     name: &'static str,
     version: &'static str,
 }
 
-#[derive(Serialize)]
+impl ToolInfo {
+    const fn new() -> Self {
+        Self {
+            name: env!("CARGO_PKG_NAME"),
+            version: env!("CARGO_PKG_VERSION"),
+        }
+    }
+}
+
+// Totals written by the dumper in the last `HEAP SUMMARY` record.
+#[derive(Serialize, JsonSchema, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HeapSummaryInfo {
+    pub live_bytes: u64,
+    pub live_instances: u64,
+    pub allocated_bytes: u64,
+    pub allocated_instances: u64,
+}
+
+// Profiling options of the last `CONTROL SETTINGS` record.
+#[derive(Serialize, JsonSchema, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ControlSettingsInfo {
+    pub allocation_traces: bool,
+    pub cpu_sampling: bool,
+    pub stack_trace_depth: u16,
+}
+
+impl ControlSettingsInfo {
+    pub const fn new(flags: u32, stack_trace_depth: u16) -> Self {
+        Self {
+            allocation_traces: flags & 0x1 != 0,
+            cpu_sampling: flags & 0x2 != 0,
+            stack_trace_depth,
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
 pub struct DumpInfo {
     file: String,
     file_size_bytes: u64,
@@ -49,6 +236,10 @@ pub struct DumpInfo {
     id_size_bytes: u32,
     captured_at_epoch_millis: Option<u64>,
     captured_at_utc: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    heap_summary: Option<HeapSummaryInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    control_settings: Option<ControlSettingsInfo>,
 }
 
 impl DumpInfo {
@@ -70,27 +261,130 @@ impl DumpInfo {
             id_size_bytes,
             captured_at_epoch_millis,
             captured_at_utc,
+            heap_summary: None,
+            control_settings: None,
         }
     }
+
+    pub const fn with_records(
+        mut self,
+        heap_summary: Option<HeapSummaryInfo>,
+        control_settings: Option<ControlSettingsInfo>,
+    ) -> Self {
+        self.heap_summary = heap_summary;
+        self.control_settings = control_settings;
+        self
+    }
+
+    // Labelled values for the reports meant to be read, e.g. `--html`.
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = vec![
+            ("File", self.file.clone()),
+            ("File size", pretty_bytes_size(self.file_size_bytes)),
+            ("Format", self.format.clone()),
+            ("Identifier size", format!("{} bytes", self.id_size_bytes)),
+        ];
+        if let Some(captured_at_utc) = &self.captured_at_utc {
+            fields.push(("Captured at", captured_at_utc.clone()));
+        }
+        if let Some(summary) = self.heap_summary {
+            fields.push((
+                "Heap summary",
+                format!(
+                    "{} live in {} instances, {} allocated in {} instances",
+                    pretty_bytes_size(summary.live_bytes),
+                    summary.live_instances,
+                    pretty_bytes_size(summary.allocated_bytes),
+                    summary.allocated_instances
+                ),
+            ));
+        }
+        if let Some(settings) = self.control_settings {
+            fields.push((
+                "Control settings",
+                format!(
+                    "allocation traces {}, CPU sampling {}, stack trace depth {}",
+                    if settings.allocation_traces {
+                        "on"
+                    } else {
+                        "off"
+                    },
+                    if settings.cpu_sampling { "on" } else { "off" },
+                    settings.stack_trace_depth
+                ),
+            ));
+        }
+        fields
+    }
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 struct HeapInfo {
     total_shallow_bytes: u64,
     class_count: usize,
     top_allocated_classes: Vec<ClassAllocationStats>,
     top_largest_instances: Vec<ClassAllocationStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    array_size_distribution: Option<Vec<ArrayDistribution>>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct JsonResult {
     schema_version: u32,
     tool: ToolInfo,
     dump: DumpInfo,
+    records: RecordCounts,
     heap: HeapInfo,
+    threads: Vec<ThreadStack>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thread_groups: Option<Vec<ThreadGroup>>,
+    // the optional reports, each only with its flag
+    #[serde(skip_serializing_if = "Option::is_none")]
+    heaps: Option<Vec<HeapUsage>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dumps: Option<DumpReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    android: Option<AndroidReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    alloc_traces: Option<AllocationTraceReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gc_roots: Option<GcRootReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    locks: Option<MonitorReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thread_lifecycle: Option<ThreadLifecycleReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duplicated_strings: Option<DuplicatedStrings>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    zero_arrays: Option<ZeroArrayReport>,
+    // only with `--list-strings`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    strings: Option<Vec<String>>,
+    warnings: WarningsInfo,
 }
 
 impl JsonResult {
+    // Every section of the analysis, the optional reports included: the text
+    // output is rendered afterwards from the same result.
+    pub fn from_rendered(dump: DumpInfo, rendered_result: &mut RenderedResult, top: usize) -> Self {
+        Self::new(dump, &mut rendered_result.memory_usage, top)
+            .with_records(rendered_result.record_counts.clone())
+            .with_array_sizes(rendered_result.array_sizes.clone())
+            .with_threads(rendered_result.threads.clone())
+            .with_thread_groups(rendered_result.thread_groups.clone())
+            .with_heaps(rendered_result.heaps.clone())
+            .with_dumps(rendered_result.dumps.clone())
+            .with_android(rendered_result.android.clone())
+            .with_alloc_traces(rendered_result.alloc_traces.clone())
+            .with_gc_roots(rendered_result.gc_roots.clone())
+            .with_locks(rendered_result.locks.clone())
+            .with_thread_lifecycle(rendered_result.thread_lifecycle.clone())
+            .with_duplicated_strings(rendered_result.duplicated_strings.clone())
+            .with_zero_arrays(rendered_result.zero_arrays.clone())
+            .with_strings(rendered_result.captured_strings.clone())
+            .with_warnings(rendered_result.warnings_info.clone())
+    }
+
     pub fn new(dump: DumpInfo, memory_usage: &mut [ClassAllocationStats], top: usize) -> Self {
         // totals over all classes, not only the top entries
         let total_shallow_bytes = memory_usage
@@ -106,66 +400,250 @@ impl JsonResult {
         let top_largest_instances = memory_usage.iter().take(top).cloned().collect();
         Self {
             schema_version: JSON_SCHEMA_VERSION,
-            tool: ToolInfo {
-                name: env!("CARGO_PKG_NAME"),
-                version: env!("CARGO_PKG_VERSION"),
-            },
+            tool: ToolInfo::new(),
             dump,
+            records: RecordCounts::default(),
             heap: HeapInfo {
                 total_shallow_bytes,
                 class_count,
                 top_allocated_classes,
                 top_largest_instances,
+                array_size_distribution: None,
             },
+            threads: Vec::new(),
+            thread_groups: None,
+            heaps: None,
+            dumps: None,
+            android: None,
+            alloc_traces: None,
+            gc_roots: None,
+            locks: None,
+            thread_lifecycle: None,
+            duplicated_strings: None,
+            zero_arrays: None,
+            strings: None,
+            warnings: WarningsInfo::default(),
         }
     }
 
+    pub fn with_records(mut self, records: RecordCounts) -> Self {
+        self.records = records;
+        self
+    }
+
+    pub fn with_threads(mut self, threads: Vec<ThreadStack>) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    pub fn with_duplicated_strings(
+        mut self,
+        duplicated_strings: Option<DuplicatedStrings>,
+    ) -> Self {
+        self.duplicated_strings = duplicated_strings;
+        self
+    }
+
+    pub fn with_strings(mut self, strings: Option<Vec<String>>) -> Self {
+        self.strings = strings;
+        self
+    }
+
+    pub fn with_warnings(mut self, warnings: WarningsInfo) -> Self {
+        self.warnings = warnings;
+        self
+    }
+
+    // Distribution of every array type, not only the top ones.
+    pub fn with_array_sizes(mut self, array_sizes: Option<Vec<ArrayDistribution>>) -> Self {
+        self.heap.array_size_distribution = array_sizes;
+        self
+    }
+
+    pub fn with_thread_groups(mut self, thread_groups: Option<Vec<ThreadGroup>>) -> Self {
+        self.thread_groups = thread_groups;
+        self
+    }
+
+    // Complete histogram of each heap, not only the top classes.
+    pub fn with_heaps(mut self, heaps: Option<Vec<HeapUsage>>) -> Self {
+        self.heaps = heaps;
+        self
+    }
+
+    // Complete histogram of each dump of the file, not only the top classes.
+    pub fn with_dumps(mut self, dumps: Option<DumpReport>) -> Self {
+        self.dumps = dumps;
+        self
+    }
+
+    pub fn with_android(mut self, android: Option<AndroidReport>) -> Self {
+        self.android = android;
+        self
+    }
+
+    pub fn with_alloc_traces(mut self, alloc_traces: Option<AllocationTraceReport>) -> Self {
+        self.alloc_traces = alloc_traces;
+        self
+    }
+
+    pub fn with_gc_roots(mut self, gc_roots: Option<GcRootReport>) -> Self {
+        self.gc_roots = gc_roots;
+        self
+    }
+
+    pub fn with_locks(mut self, locks: Option<MonitorReport>) -> Self {
+        self.locks = locks;
+        self
+    }
+
+    pub fn with_thread_lifecycle(
+        mut self,
+        thread_lifecycle: Option<ThreadLifecycleReport>,
+    ) -> Self {
+        self.thread_lifecycle = thread_lifecycle;
+        self
+    }
+
+    pub fn with_zero_arrays(mut self, zero_arrays: Option<ZeroArrayReport>) -> Self {
+        self.zero_arrays = zero_arrays;
+        self
+    }
+
     pub fn save_as_file(&self, output_path: Option<&str>) -> Result<(), HprofSlurpError> {
-        let file_path = output_path.map_or_else(
-            || {
-                let millis = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .expect("system clock should be set after 1970")
-                    .as_millis();
-                format!("hprof-slurp-{millis}.json")
-            },
-            str::to_string,
-        );
-        let file = File::create(&file_path)?;
-        let writer = BufWriter::new(file);
-        // Serialize the struct directly to the file via the writer
-        serde_json::to_writer(writer, &self)?;
-        println!("Output JSON result file {file_path}");
-        Ok(())
+        save_json_file(self, output_path)
+    }
+}
+
+// `diff --json`: every class delta between the two dumps, not only the top.
+#[derive(Serialize, JsonSchema)]
+pub struct JsonDiffResult {
+    schema_version: u32,
+    tool: ToolInfo,
+    from: DumpInfo,
+    to: DumpInfo,
+    totals: DiffTotals,
+    entries: Vec<DiffEntry>,
+}
+
+impl JsonDiffResult {
+    pub const fn new(
+        from: DumpInfo,
+        to: DumpInfo,
+        totals: DiffTotals,
+        entries: Vec<DiffEntry>,
+    ) -> Self {
+        Self {
+            schema_version: JSON_SCHEMA_VERSION,
+            tool: ToolInfo::new(),
+            from,
+            to,
+            totals,
+            entries,
+        }
+    }
+
+    pub fn save_as_file(&self, output_path: Option<&str>) -> Result<(), HprofSlurpError> {
+        save_json_file(self, output_path)
     }
 }
 
 pub struct RenderedResult {
     pub summary: String,
+    pub record_counts: RecordCounts,
+    pub heap_summary: Option<HeapSummaryInfo>,
+    pub control_settings: Option<ControlSettingsInfo>,
     pub thread_info: String,
+    pub threads: Vec<ThreadStack>,
+    // only with `--group-threads`, `thread_info` then renders the groups
+    pub thread_groups: Option<Vec<ThreadGroup>>,
     pub memory_usage: Vec<ClassAllocationStats>,
-    pub duplicated_strings: Option<String>,
-    pub captured_strings: Option<String>,
+    pub array_sizes: Option<Vec<ArrayDistribution>>,
+    pub heaps: Option<Vec<HeapUsage>>,
+    pub dumps: Option<DumpReport>,
+    pub android: Option<AndroidReport>,
+    pub alloc_traces: Option<AllocationTraceReport>,
+    // written to its own file, see `--alloc-collapsed`
+    pub alloc_collapsed: Option<String>,
+    pub gc_roots: Option<GcRootReport>,
+    pub locks: Option<MonitorReport>,
+    pub thread_lifecycle: Option<ThreadLifecycleReport>,
+    // written to its own file, see `--jstack`
+    pub jstack: Option<String>,
+    pub duplicated_strings: Option<DuplicatedStrings>,
+    pub zero_arrays: Option<ZeroArrayReport>,
+    // sorted, only with `--list-strings`
+    pub captured_strings: Option<Vec<String>>,
     pub warnings: Option<String>,
+    pub warnings_info: WarningsInfo,
 }
 
 impl RenderedResult {
     pub fn serialize(self, top: usize) -> String {
         let Self {
             summary,
+            record_counts: _,
+            heap_summary: _,
+            control_settings: _,
             thread_info,
+            threads: _,
+            thread_groups: _,
             mut memory_usage,
+            array_sizes,
+            heaps,
+            dumps,
+            android,
+            alloc_traces,
+            alloc_collapsed: _,
+            gc_roots,
+            locks,
+            thread_lifecycle,
+            jstack: _,
             duplicated_strings,
+            zero_arrays,
             captured_strings,
             warnings,
+            warnings_info: _,
         } = self;
         let memory = Self::render_memory_usage(&mut memory_usage, top);
         let mut result = format!("{summary}\n{thread_info}\n{memory}");
+        if let Some(array_sizes) = array_sizes {
+            write!(result, "{}", ArrayDistribution::render(&array_sizes, top))
+                .expect("write should not fail");
+        }
+        if let Some(mut heaps) = heaps {
+            write!(result, "{}", HeapUsage::render(&mut heaps, top))
+                .expect("write should not fail");
+        }
+        if let Some(mut dumps) = dumps {
+            write!(result, "{}", dumps.render(top)).expect("write should not fail");
+        }
+        if let Some(android) = android {
+            write!(result, "{}", android.render(top)).expect("write should not fail");
+        }
+        if let Some(alloc_traces) = alloc_traces {
+            write!(result, "{}", alloc_traces.render(top)).expect("write should not fail");
+        }
+        if let Some(gc_roots) = gc_roots {
+            write!(result, "{}", gc_roots.render(top)).expect("write should not fail");
+        }
+        if let Some(locks) = locks {
+            write!(result, "{}", locks.render()).expect("write should not fail");
+        }
+        if let Some(thread_lifecycle) = thread_lifecycle {
+            write!(result, "{}", thread_lifecycle.render()).expect("write should not fail");
+        }
         if let Some(duplicated_strings) = duplicated_strings {
-            writeln!(result, "{duplicated_strings}").expect("write should not fail");
+            writeln!(result, "{}", duplicated_strings.render()).expect("write should not fail");
+        }
+        if let Some(zero_arrays) = zero_arrays {
+            write!(result, "{}", zero_arrays.render()).expect("write should not fail");
         }
         if let Some(list_strings) = captured_strings {
-            write!(result, "{list_strings}").expect("write should not fail");
+            writeln!(result, "\nList of Strings").expect("write should not fail");
+            for s in list_strings {
+                writeln!(result, "{s}").expect("write should not fail");
+            }
         }
         // last so it stays visible even when `--list-strings` floods the output
         if let Some(warnings) = warnings {
@@ -206,7 +684,7 @@ impl RenderedResult {
     }
 
     // Render table from [(class_name, count, largest_allocation, instance_size)]
-    fn render_table(top: usize, analysis: &mut String, rows: &[ClassAllocationStats]) {
+    pub fn render_table(top: usize, analysis: &mut String, rows: &[ClassAllocationStats]) {
         let rows_formatted: Vec<_> = rows
             .iter()
             .take(top)
@@ -355,6 +833,7 @@ impl RenderedResult {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::array_distribution::LengthHistogram;
 
     #[test]
     fn text_output_describes_raw_shallow_dump_objects() {
@@ -384,7 +863,7 @@ mod tests {
         let json_result = JsonResult::new(dump_info, &mut memory_usage, 1);
         let json = serde_json::to_value(&json_result).expect("should serialize");
 
-        assert_eq!(json["schema_version"], 1);
+        assert_eq!(json["schema_version"], 2);
         assert_eq!(json["tool"]["name"], "hprof-slurp");
         assert_eq!(json["dump"]["file"], "heap.hprof");
         assert_eq!(json["dump"]["file_size_bytes"], 1234);
@@ -407,6 +886,118 @@ mod tests {
         );
     }
 
+    #[test]
+    fn json_result_carries_the_default_sections_as_data() {
+        let mut memory_usage = vec![ClassAllocationStats::new("A".to_string(), 1, 16, 16)];
+        let dump_info = DumpInfo::new(
+            "heap.hprof".to_string(),
+            1234,
+            "JAVA PROFILE 1.0.2".to_string(),
+            8,
+            0,
+        );
+        let records = RecordCounts {
+            utf8_strings: 3,
+            gc_roots: GcRootCounts {
+                java_frame: 5,
+                ..GcRootCounts::default()
+            },
+            ..RecordCounts::default()
+        };
+        let threads = vec![ThreadStack {
+            name: "main".to_string(),
+            thread_serial_number: 1,
+            stack_trace_serial_number: 2,
+            frames: vec!["Main.main() (Main.java:3)".to_string()],
+        }];
+
+        let json_result = JsonResult::new(dump_info, &mut memory_usage, 1)
+            .with_records(records)
+            .with_threads(threads)
+            .with_warnings(WarningsInfo {
+                missing_class_ids: vec![0x10],
+                undecodable_utf8_sequences: 0,
+            });
+        let json = serde_json::to_value(&json_result).expect("should serialize");
+
+        assert_eq!(json["records"]["utf8_strings"], 3);
+        assert_eq!(json["records"]["gc_roots"]["java_frame"], 5);
+        assert_eq!(json["threads"][0]["name"], "main");
+        assert_eq!(json["threads"][0]["frames"][0], "Main.main() (Main.java:3)");
+        assert_eq!(json["warnings"]["missing_class_ids"][0], 0x10);
+        // without `--list-strings` and duplicates
+        assert!(json.get("strings").is_none());
+        assert!(json.get("duplicated_strings").is_none());
+    }
+
+    #[test]
+    fn published_schema_is_up_to_date() {
+        let published = std::fs::read_to_string("schema/hprof-slurp.schema.json")
+            .expect("published schema not found");
+        let published: serde_json::Value =
+            serde_json::from_str(&published).expect("published schema should be JSON");
+        assert!(
+            published == json_schema(),
+            "the JSON output changed: bump JSON_SCHEMA_VERSION on a breaking change and regenerate schema/hprof-slurp.schema.json"
+        );
+    }
+
+    #[test]
+    fn published_diff_schema_is_up_to_date() {
+        let published = std::fs::read_to_string("schema/hprof-slurp-diff.schema.json")
+            .expect("published schema not found");
+        let published: serde_json::Value =
+            serde_json::from_str(&published).expect("published schema should be JSON");
+        assert!(
+            published == diff_json_schema(),
+            "the diff JSON output changed: bump JSON_SCHEMA_VERSION on a breaking change and regenerate schema/hprof-slurp-diff.schema.json"
+        );
+    }
+
+    #[test]
+    fn json_array_size_distribution_only_when_requested() {
+        let dump_info = || DumpInfo::new("heap.hprof".to_string(), 1, "F".to_string(), 4, 0);
+        let mut memory_usage = vec![ClassAllocationStats::new("int[]".to_string(), 1, 16, 16)];
+
+        let json_result = JsonResult::new(dump_info(), &mut memory_usage, 1).with_array_sizes(None);
+        let json = serde_json::to_value(&json_result).expect("should serialize");
+        assert!(json["heap"].get("array_size_distribution").is_none());
+
+        let mut histogram = LengthHistogram::empty();
+        histogram.add_array(1, 16);
+        let distribution = histogram.distribution("int[]".to_string(), |_| 16);
+        let json_result = JsonResult::new(dump_info(), &mut memory_usage, 1)
+            .with_array_sizes(Some(vec![distribution]));
+        let json = serde_json::to_value(&json_result).expect("should serialize");
+        let distribution = &json["heap"]["array_size_distribution"][0];
+        assert_eq!(distribution["array_type"], "int[]");
+        assert_eq!(distribution["p99"]["max_elements"], 1);
+        assert_eq!(distribution["buckets"][0]["arrays"], 1);
+    }
+
+    #[test]
+    fn json_thread_groups_only_when_requested() {
+        let dump_info = || DumpInfo::new("heap.hprof".to_string(), 1, "F".to_string(), 4, 0);
+        let mut memory_usage = vec![];
+
+        let json_result = JsonResult::new(dump_info(), &mut memory_usage, 1);
+        let json = serde_json::to_value(&json_result).expect("should serialize");
+        assert!(json.get("thread_groups").is_none());
+
+        let group = ThreadGroup {
+            threads: vec!["main".to_string()],
+            frames: vec!["com.example.Main.main (Main.java:3)".to_string()],
+        };
+        let json_result = JsonResult::new(dump_info(), &mut memory_usage, 1)
+            .with_thread_groups(Some(vec![group]));
+        let json = serde_json::to_value(&json_result).expect("should serialize");
+        assert_eq!(json["thread_groups"][0]["threads"][0], "main");
+        assert_eq!(
+            json["thread_groups"][0]["frames"][0],
+            "com.example.Main.main (Main.java:3)"
+        );
+    }
+
     #[test]
     fn json_capture_time_is_null_when_absent() {
         let dump_info = DumpInfo::new("heap.hprof".to_string(), 1, "F".to_string(), 4, 0);
@@ -421,11 +1012,28 @@ mod tests {
     fn serialize_appends_warnings_last() {
         let rendered_result = RenderedResult {
             summary: "summary".to_string(),
+            record_counts: RecordCounts::default(),
+            heap_summary: None,
+            control_settings: None,
             thread_info: "threads".to_string(),
+            threads: Vec::new(),
+            thread_groups: None,
             memory_usage: vec![ClassAllocationStats::new("Thing".to_string(), 1, 16, 16)],
+            array_sizes: None,
+            heaps: None,
+            dumps: None,
+            android: None,
+            alloc_traces: None,
+            alloc_collapsed: None,
+            gc_roots: None,
+            locks: None,
+            thread_lifecycle: None,
+            jstack: None,
             duplicated_strings: None,
-            captured_strings: Some("strings".to_string()),
+            zero_arrays: None,
+            captured_strings: Some(vec!["strings".to_string()]),
             warnings: Some("\nWarning: something was off\n".to_string()),
+            warnings_info: WarningsInfo::default(),
         };
 
         let output = rendered_result.serialize(1);
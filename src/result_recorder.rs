@@ -5,26 +5,43 @@ use std::fmt::Write;
 use std::thread::JoinHandle;
 use std::{mem, thread};
 
-use crate::parser::gc_record::{FieldType, GcRecord};
+use crate::alloc_traces::{AllocationTrace, AllocationTraceReport};
+use crate::android::{AndroidClass, AndroidComponent, AndroidReport, Bitmap};
+use crate::args::ReportOptions;
+use crate::array_distribution::{ArrayDistribution, LengthHistogram};
+use crate::descriptor::{decode_class_name, decode_method_parameters};
+use crate::dumps::{DumpReport, DumpUsage};
+use crate::gc_roots::{GcRoot, GcRootReport, ObjectShape, RootKind};
+use crate::heaps::{DEFAULT_HEAP_TYPE, HeapUsage, default_heap_name, is_shared_heap};
+use crate::jstack::{self, JstackFrame, JstackMonitor, JstackThread};
+use crate::locks::{Monitor, MonitorReference, MonitorReport};
+use crate::parser::gc_record::{FieldInfo, FieldType, FieldValue, GcRecord, primitive_byte_size};
 use crate::parser::record::Record::{
     AllocationSites, ControlSettings, CpuSamples, EndThread, GcSegment, HeapDumpEnd, HeapDumpStart,
     HeapSummary, LoadClass, StackFrame, StackTrace, StartThread, UnloadClass, Utf8String,
 };
 use crate::parser::record::{LoadClassData, Record, StackFrameData, StackTraceData};
-use crate::rendered_result::{ClassAllocationStats, RenderedResult};
-use crate::utils::pretty_timestamp_utc;
+use crate::parser::record_parser::parse_field_value;
+use crate::rendered_result::{
+    ClassAllocationStats, ControlSettingsInfo, DuplicatedString, DuplicatedStrings, GcRootCounts,
+    HeapSummaryInfo, RecordCounts, RenderedResult, ThreadStack, WarningsInfo,
+};
+use crate::thread_groups::ThreadGroup;
+use crate::thread_lifecycle::{ThreadLifecycle, ThreadLifecycleReport};
+use crate::utils::{pretty_bytes_size, pretty_timestamp_utc};
+use crate::zero_arrays::{ZeroArray, ZeroArrayRecorder};
 
 #[derive(Debug)]
 struct ClassInfo {
     super_class_object_id: u64,
-    instance_field_types: Vec<FieldType>,
+    instance_fields: Vec<FieldInfo>,
 }
 
 impl ClassInfo {
-    fn new(super_class_object_id: u64, instance_field_types: Vec<FieldType>) -> Self {
+    fn new(super_class_object_id: u64, instance_fields: Vec<FieldInfo>) -> Self {
         Self {
             super_class_object_id,
-            instance_field_types,
+            instance_fields,
         }
     }
 }
@@ -46,27 +63,134 @@ impl ClassInstanceCounter {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Clone)]
 struct ArrayCounter {
     number_of_arrays: u64,
     max_size_bytes_seen: u64,
     total_size_bytes: u64,
+    // only with `--array-sizes`
+    length_histogram: Option<Box<LengthHistogram>>,
 }
 
 impl ArrayCounter {
-    fn add_array(&mut self, size_bytes: u64) {
+    fn add_array(&mut self, number_of_elements: u32, size_bytes: u64) {
         self.number_of_arrays += 1;
         if size_bytes > self.max_size_bytes_seen {
             self.max_size_bytes_seen = size_bytes;
         }
         self.total_size_bytes += size_bytes;
+        if let Some(histogram) = &mut self.length_histogram {
+            histogram.add_array(number_of_elements, size_bytes);
+        }
     }
 
-    const fn empty() -> Self {
+    fn merge(&mut self, other: &Self) {
+        self.number_of_arrays += other.number_of_arrays;
+        self.max_size_bytes_seen = self.max_size_bytes_seen.max(other.max_size_bytes_seen);
+        self.total_size_bytes += other.total_size_bytes;
+        if let (Some(histogram), Some(other)) =
+            (&mut self.length_histogram, &other.length_histogram)
+        {
+            histogram.merge(other);
+        }
+    }
+
+    fn empty(length_histogram: bool) -> Self {
         Self {
             number_of_arrays: 0,
             max_size_bytes_seen: 0,
             total_size_bytes: 0,
+            length_histogram: length_histogram.then(|| Box::new(LengthHistogram::empty())),
+        }
+    }
+}
+
+// Start and end records of one thread, see `--thread-lifecycle`.
+#[derive(Default)]
+struct ThreadRecords {
+    object_id: u64,
+    group_name_id: u64,
+    // record timestamps in microseconds since the header one
+    started_at: Option<u32>,
+    ended_at: Option<u32>,
+    // the `java.lang.Thread` instance is dumped
+    object_in_heap: bool,
+}
+
+// Histogram counters of the objects of one heap, see `HeapDumpInfo`.
+struct HeapCounters {
+    heap_type: u32,
+    // `0` when the dumper did not name the heap
+    heap_name_id: u64,
+    classes_all_instance_total_size_by_id: AHashMap<u64, ClassInstanceCounter>,
+    primitive_array_counters: AHashMap<FieldType, ArrayCounter>,
+    object_array_counters: AHashMap<u64, ArrayCounter>,
+    // whether the array counters keep a length histogram
+    array_sizes: bool,
+}
+
+impl HeapCounters {
+    fn new(heap_type: u32, heap_name_id: u64, array_sizes: bool) -> Self {
+        Self {
+            heap_type,
+            heap_name_id,
+            array_sizes,
+            classes_all_instance_total_size_by_id: AHashMap::new(),
+            primitive_array_counters: AHashMap::new(),
+            object_array_counters: AHashMap::new(),
+        }
+    }
+
+    fn add_instance(&mut self, class_object_id: u64) {
+        self.classes_all_instance_total_size_by_id
+            .entry(class_object_id)
+            .or_insert_with(ClassInstanceCounter::empty)
+            .add_instance();
+    }
+
+    fn add_object_array(&mut self, array_class_id: u64, number_of_elements: u32, size_bytes: u64) {
+        self.object_array_counters
+            .entry(array_class_id)
+            .or_insert_with(|| ArrayCounter::empty(self.array_sizes))
+            .add_array(number_of_elements, size_bytes);
+    }
+
+    fn add_primitive_array(
+        &mut self,
+        element_type: FieldType,
+        number_of_elements: u32,
+        size_bytes: u64,
+    ) {
+        self.primitive_array_counters
+            .entry(element_type)
+            .or_insert_with(|| ArrayCounter::empty(self.array_sizes))
+            .add_array(number_of_elements, size_bytes);
+    }
+
+    fn is_empty(&self) -> bool {
+        self.classes_all_instance_total_size_by_id.is_empty()
+            && self.primitive_array_counters.is_empty()
+            && self.object_array_counters.is_empty()
+    }
+
+    fn merge(&mut self, other: &Self) {
+        for (class_id, counter) in &other.classes_all_instance_total_size_by_id {
+            self.classes_all_instance_total_size_by_id
+                .entry(*class_id)
+                .or_insert_with(ClassInstanceCounter::empty)
+                .number_of_instances += counter.number_of_instances;
+        }
+        for (field_type, counter) in &other.primitive_array_counters {
+            self.primitive_array_counters
+                .entry(*field_type)
+                .or_insert_with(|| ArrayCounter::empty(self.array_sizes))
+                .merge(counter);
+        }
+        for (class_id, counter) in &other.object_array_counters {
+            self.object_array_counters
+                .entry(*class_id)
+                .or_insert_with(|| ArrayCounter::empty(self.array_sizes))
+                .merge(counter);
         }
     }
 }
@@ -74,7 +198,7 @@ impl ArrayCounter {
 pub struct ResultRecorder {
     // Recorder's params
     id_size: u32,
-    list_strings: bool,
+    reports: ReportOptions,
     // Capture time of the dump in epoch milliseconds (`0` when absent)
     timestamp: u64,
     // Tag counters
@@ -88,6 +212,11 @@ pub struct ResultRecorder {
     allocation_sites: u32,
     control_settings: u32,
     cpu_samples: u32,
+    // content of the last heap summary and control settings records
+    heap_summary: Option<HeapSummaryInfo>,
+    control_settings_info: Option<ControlSettingsInfo>,
+    // modified UTF-8 sequences replaced by U+FFFD in the strings
+    undecodable_utf8_sequences: u64,
     // GC tag counters
     heap_dump_segments_all_sub_records: u32,
     heap_dump_segments_gc_root_unknown: u32,
@@ -99,6 +228,13 @@ pub struct ResultRecorder {
     heap_dump_segments_gc_root_sticky_class: u32,
     heap_dump_segments_gc_root_thread_block: u32,
     heap_dump_segments_gc_root_monitor_used: u32,
+    heap_dump_segments_gc_root_interned_string: u32,
+    heap_dump_segments_gc_root_finalizing: u32,
+    heap_dump_segments_gc_root_debugger: u32,
+    heap_dump_segments_gc_root_reference_cleanup: u32,
+    heap_dump_segments_gc_root_vm_internal: u32,
+    heap_dump_segments_gc_root_jni_monitor: u32,
+    heap_dump_segments_gc_unreachable: u32,
     heap_dump_segments_gc_object_array_dump: u32,
     heap_dump_segments_gc_instance_dump: u32,
     heap_dump_segments_gc_primitive_array_dump: u32,
@@ -110,18 +246,46 @@ pub struct ResultRecorder {
     class_data_by_id: AHashMap<u64, usize>, // value is index into class_data
     class_data_by_serial_number: AHashMap<u32, usize>, // value is index into class_data
     classes_single_instance_size_by_id: AHashMap<u64, ClassInfo>,
-    classes_all_instance_total_size_by_id: AHashMap<u64, ClassInstanceCounter>,
-    primitive_array_counters: AHashMap<FieldType, ArrayCounter>,
-    object_array_counters: AHashMap<u64, ArrayCounter>,
+    // one per heap in order of appearance, the objects go to `current_heap`
+    heaps: Vec<HeapCounters>,
+    current_heap: usize,
+    // `(record timestamp, counters)` per heap dump of the file, only with `--dumps`
+    dumps: Vec<(u32, HeapCounters)>,
+    // `--classloader-column`: class id -> loader object id, and loader
+    // object id -> class of the loader once its instance is dumped
+    class_loader_by_class_id: AHashMap<u64, u64>,
+    class_loader_class_by_id: AHashMap<u64, u64>,
+    // `--alloc-traces`: counters by allocating stack trace serial number
+    alloc_trace_counters: AHashMap<u32, HeapCounters>,
+    in_segmented_dump: bool,
     stack_trace_by_serial_number: AHashMap<u32, StackTraceData>,
     stack_frame_by_id: AHashMap<u64, StackFrameData>,
+    thread_name_id_by_serial_number: AHashMap<u32, u64>,
+    thread_object_id_by_serial_number: AHashMap<u32, u64>,
+    // `--thread-lifecycle`
+    thread_records_by_serial_number: AHashMap<u32, ThreadRecords>,
+    thread_serial_number_by_object_id: AHashMap<u64, u32>,
+    // Optional reports state
+    zero_arrays: Option<ZeroArrayRecorder>,
+    gc_roots: Vec<GcRoot>,
+    // `(object id, class id, field values)` of the instances that may be
+    // tracked Android classes, resolved once all the classes are known
+    android_instances: Vec<(u64, u64, Vec<u8>)>,
+    // `--android`: the tracked framework classes by class id, from their
+    // `LoadClass` name, and the resolved class of every hierarchy seen so far
+    android_framework_classes: AHashMap<u64, AndroidClass>,
+    android_class_by_id: AHashMap<u64, Option<AndroidClass>>,
+    // Shape of every heap object, only kept when a report must resolve rooted
+    // object ids (HotSpot writes the roots after the objects they reference).
+    index_objects: bool,
+    object_shapes: AHashMap<u64, ObjectShape>,
 }
 
 impl ResultRecorder {
-    pub fn new(id_size: u32, list_strings: bool, timestamp: u64) -> Self {
+    pub fn new(id_size: u32, reports: ReportOptions, timestamp: u64) -> Self {
         Self {
             id_size,
-            list_strings,
+            reports,
             timestamp,
             classes_unloaded: 0,
             stack_frames: 0,
@@ -133,6 +297,9 @@ impl ResultRecorder {
             allocation_sites: 0,
             control_settings: 0,
             cpu_samples: 0,
+            heap_summary: None,
+            control_settings_info: None,
+            undecodable_utf8_sequences: 0,
             heap_dump_segments_all_sub_records: 0,
             heap_dump_segments_gc_root_unknown: 0,
             heap_dump_segments_gc_root_thread_object: 0,
@@ -143,6 +310,13 @@ impl ResultRecorder {
             heap_dump_segments_gc_root_sticky_class: 0,
             heap_dump_segments_gc_root_thread_block: 0,
             heap_dump_segments_gc_root_monitor_used: 0,
+            heap_dump_segments_gc_root_interned_string: 0,
+            heap_dump_segments_gc_root_finalizing: 0,
+            heap_dump_segments_gc_root_debugger: 0,
+            heap_dump_segments_gc_root_reference_cleanup: 0,
+            heap_dump_segments_gc_root_vm_internal: 0,
+            heap_dump_segments_gc_root_jni_monitor: 0,
+            heap_dump_segments_gc_unreachable: 0,
             heap_dump_segments_gc_object_array_dump: 0,
             heap_dump_segments_gc_primitive_array_dump: 0,
             heap_dump_segments_gc_instance_dump: 0,
@@ -152,11 +326,32 @@ impl ResultRecorder {
             class_data_by_id: AHashMap::new(),
             class_data_by_serial_number: AHashMap::default(),
             classes_single_instance_size_by_id: AHashMap::new(),
-            classes_all_instance_total_size_by_id: AHashMap::new(),
-            primitive_array_counters: AHashMap::new(),
-            object_array_counters: AHashMap::new(),
+            heaps: vec![HeapCounters::new(DEFAULT_HEAP_TYPE, 0, reports.array_sizes)],
+            current_heap: 0,
+            dumps: vec![],
+            class_loader_by_class_id: AHashMap::new(),
+            class_loader_class_by_id: AHashMap::new(),
+            alloc_trace_counters: AHashMap::new(),
+            in_segmented_dump: false,
             stack_trace_by_serial_number: AHashMap::default(),
             stack_frame_by_id: AHashMap::default(),
+            thread_name_id_by_serial_number: AHashMap::default(),
+            thread_object_id_by_serial_number: AHashMap::default(),
+            thread_records_by_serial_number: AHashMap::default(),
+            thread_serial_number_by_object_id: AHashMap::default(),
+            zero_arrays: reports
+                .zero_arrays
+                .then(|| ZeroArrayRecorder::new(reports.mostly_zero_percent)),
+            gc_roots: vec![],
+            android_instances: vec![],
+            android_framework_classes: AHashMap::new(),
+            android_class_by_id: AHashMap::new(),
+            index_objects: reports.gc_roots
+                || reports.locals
+                || reports.locks
+                || reports.jstack
+                || reports.android,
+            object_shapes: AHashMap::new(),
         }
     }
 
@@ -175,7 +370,7 @@ impl ResultRecorder {
             .and_then(|data_index| self.class_data.get(*data_index))
             .and_then(|class_data| self.utf8_strings_by_id.get(&class_data.class_name_id))
         {
-            Some(class_name) => class_name.replace('/', "."),
+            Some(class_name) => decode_class_name(class_name),
             None => {
                 missing_class_ids.insert(class_id);
                 format!("<unknown class 0x{class_id:x}>")
@@ -202,20 +397,108 @@ impl ResultRecorder {
                     } else {
                         // no more Record to pull, generate and send back results
                         let mut missing_class_ids = AHashSet::new();
-                        let thread_info = self.render_thread_info(&mut missing_class_ids);
-                        let memory_usage = self.aggregate_memory_usage(&mut missing_class_ids);
-                        let warnings = render_missing_class_warning(&missing_class_ids);
+                        let threads = self.thread_stacks(&mut missing_class_ids);
+                        let thread_groups = self.reports.group_threads.then(|| {
+                            ThreadGroup::group(
+                                threads
+                                    .iter()
+                                    .map(|thread| (thread.name.clone(), thread.frames.clone()))
+                                    .collect(),
+                            )
+                        });
+                        let thread_info = match &thread_groups {
+                            Some(thread_groups) => ThreadGroup::render(thread_groups),
+                            None => self.render_thread_info(&mut missing_class_ids),
+                        };
+                        let counted_heaps = self.counted_heaps();
+                        let memory_usage =
+                            self.aggregate_memory_usage(&counted_heaps, &mut missing_class_ids);
+                        // the heap summary record covers every heap, the
+                        // excluded shared heaps included
+                        let all_heaps_usage = (self.reports.exclude_shared_heaps
+                            && self.heap_summary.is_some())
+                        .then(|| {
+                            self.aggregate_memory_usage(&self.all_heaps(), &mut missing_class_ids)
+                        });
+                        let array_sizes = self.reports.array_sizes.then(|| {
+                            self.aggregate_array_distribution(
+                                &counted_heaps,
+                                &mut missing_class_ids,
+                            )
+                        });
+                        let heaps = self
+                            .reports
+                            .heaps
+                            .then(|| self.heap_usages(&mut missing_class_ids));
+                        let dumps = self
+                            .reports
+                            .dumps
+                            .then(|| self.dump_report(&mut missing_class_ids));
+                        let alloc_traces = (self.reports.alloc_traces
+                            || self.reports.alloc_collapsed)
+                            .then(|| self.alloc_trace_report(&mut missing_class_ids));
+                        let alloc_collapsed = alloc_traces
+                            .as_ref()
+                            .filter(|_| self.reports.alloc_collapsed)
+                            .map(AllocationTraceReport::render_collapsed);
+                        let alloc_traces = alloc_traces.filter(|_| self.reports.alloc_traces);
+                        let android = self
+                            .reports
+                            .android
+                            .then(|| self.android_report(&mut missing_class_ids));
+                        let gc_roots = self
+                            .reports
+                            .gc_roots
+                            .then(|| self.gc_root_report(&mut missing_class_ids));
+                        let locks = self
+                            .reports
+                            .locks
+                            .then(|| self.monitor_report(&mut missing_class_ids));
+                        let thread_lifecycle = self
+                            .reports
+                            .thread_lifecycle
+                            .then(|| self.thread_lifecycle_report());
+                        let jstack = self.reports.jstack.then(|| {
+                            jstack::render(
+                                self.timestamp,
+                                &self.jstack_threads(&mut missing_class_ids),
+                            )
+                        });
+                        let warnings =
+                            render_warnings(&missing_class_ids, self.undecodable_utf8_sequences);
+                        let mut missing_class_ids: Vec<_> = missing_class_ids.into_iter().collect();
+                        missing_class_ids.sort_unstable();
                         let rendered_result = RenderedResult {
-                            summary: self.render_summary(),
+                            summary: self
+                                .render_summary(all_heaps_usage.as_ref().unwrap_or(&memory_usage)),
+                            record_counts: self.record_counts(),
+                            heap_summary: self.heap_summary,
+                            control_settings: self.control_settings_info,
                             thread_info,
+                            threads,
+                            thread_groups,
                             memory_usage,
-                            duplicated_strings: self.render_duplicated_strings(),
-                            captured_strings: if self.list_strings {
-                                Some(self.render_captured_strings())
-                            } else {
-                                None
-                            },
+                            array_sizes,
+                            heaps,
+                            dumps,
+                            android,
+                            alloc_traces,
+                            alloc_collapsed,
+                            gc_roots,
+                            locks,
+                            thread_lifecycle,
+                            jstack,
+                            duplicated_strings: self.duplicated_strings(),
+                            zero_arrays: self.zero_arrays.take().map(ZeroArrayRecorder::report),
+                            captured_strings: self
+                                .reports
+                                .list_strings
+                                .then(|| self.captured_strings()),
                             warnings,
+                            warnings_info: WarningsInfo {
+                                missing_class_ids,
+                                undecodable_utf8_sequences: self.undecodable_utf8_sequences,
+                            },
                         };
                         send_result
                             .send(rendered_result)
@@ -229,12 +512,28 @@ impl ResultRecorder {
     fn record_records(&mut self, records: &mut [Record]) {
         for record in records.iter_mut() {
             match record {
-                Utf8String { id, str } => {
+                Utf8String {
+                    id,
+                    str,
+                    undecodable_sequences,
+                } => {
+                    self.undecodable_utf8_sequences += u64::from(*undecodable_sequences);
                     self.utf8_strings_by_id.insert(*id, mem::take(str));
                 }
                 LoadClass(load_class_data) => {
                     let class_object_id = load_class_data.class_object_id;
                     let class_serial_number = load_class_data.serial_number;
+                    if self.reports.android
+                        && let Some(android_class) = self
+                            .utf8_strings_by_id
+                            .get(&load_class_data.class_name_id)
+                            .and_then(|name| {
+                                AndroidClass::from_class_name(&decode_class_name(name))
+                            })
+                    {
+                        self.android_framework_classes
+                            .insert(class_object_id, android_class);
+                    }
                     self.class_data.push(mem::take(load_class_data));
                     let data_index = self.class_data.len() - 1;
                     self.class_data_by_id.insert(class_object_id, data_index);
@@ -252,21 +551,97 @@ impl ResultRecorder {
                     self.stack_trace_by_serial_number
                         .insert(stack_trace_data.serial_number, mem::take(stack_trace_data));
                 }
-                StartThread { .. } => self.start_threads += 1,
-                EndThread { .. } => self.end_threads += 1,
+                StartThread {
+                    timestamp,
+                    thread_serial_number,
+                    thread_object_id,
+                    thread_name_id,
+                    thread_group_name_id,
+                    ..
+                } => {
+                    self.start_threads += 1;
+                    self.thread_object_id_by_serial_number
+                        .insert(*thread_serial_number, *thread_object_id);
+                    self.thread_name_id_by_serial_number
+                        .insert(*thread_serial_number, *thread_name_id);
+                    if self.reports.thread_lifecycle {
+                        let records = self
+                            .thread_records_by_serial_number
+                            .entry(*thread_serial_number)
+                            .or_default();
+                        records.object_id = *thread_object_id;
+                        records.group_name_id = *thread_group_name_id;
+                        records.started_at = Some(*timestamp);
+                        self.thread_serial_number_by_object_id
+                            .insert(*thread_object_id, *thread_serial_number);
+                    }
+                }
+                EndThread {
+                    timestamp,
+                    thread_serial_number,
+                } => {
+                    self.end_threads += 1;
+                    if self.reports.thread_lifecycle {
+                        self.thread_records_by_serial_number
+                            .entry(*thread_serial_number)
+                            .or_default()
+                            .ended_at = Some(*timestamp);
+                    }
+                }
                 AllocationSites { .. } => self.allocation_sites += 1,
-                HeapSummary { .. } => self.heap_summaries += 1,
-                ControlSettings { .. } => self.control_settings += 1,
+                HeapSummary {
+                    total_live_bytes,
+                    total_live_instances,
+                    total_bytes_allocated,
+                    total_instances_allocated,
+                } => {
+                    self.heap_summaries += 1;
+                    self.heap_summary = Some(HeapSummaryInfo {
+                        live_bytes: u64::from(*total_live_bytes),
+                        live_instances: u64::from(*total_live_instances),
+                        allocated_bytes: *total_bytes_allocated,
+                        allocated_instances: *total_instances_allocated,
+                    });
+                }
+                ControlSettings {
+                    flags,
+                    stack_trace_depth,
+                } => {
+                    self.control_settings += 1;
+                    self.control_settings_info =
+                        Some(ControlSettingsInfo::new(*flags, *stack_trace_depth));
+                }
                 CpuSamples { .. } => self.cpu_samples += 1,
-                HeapDumpEnd { .. } => (),
-                HeapDumpStart { .. } => self.heap_dumps += 1,
+                HeapDumpEnd { .. } => self.in_segmented_dump = false,
+                HeapDumpStart {
+                    timestamp, segment, ..
+                } => {
+                    self.heap_dumps += 1;
+                    // segments continue the current dump until its end record
+                    if self.reports.dumps && !(*segment && self.in_segmented_dump) {
+                        self.dumps
+                            .push((*timestamp, HeapCounters::new(DEFAULT_HEAP_TYPE, 0, false)));
+                    }
+                    self.in_segmented_dump = *segment;
+                }
                 GcSegment(gc_record) => {
                     self.heap_dump_segments_all_sub_records += 1;
+                    if self.index_objects
+                        && let Some(root) = GcRoot::from_record(gc_record)
+                    {
+                        self.gc_roots.push(root);
+                    }
                     match gc_record {
                         GcRecord::RootUnknown { .. } => {
                             self.heap_dump_segments_gc_root_unknown += 1;
                         }
-                        GcRecord::RootThreadObject { .. } => {
+                        GcRecord::RootThreadObject {
+                            thread_object_id,
+                            thread_sequence_number,
+                            ..
+                        } => {
+                            self.thread_object_id_by_serial_number
+                                .insert(*thread_sequence_number, *thread_object_id);
                             self.heap_dump_segments_gc_root_thread_object += 1;
                         }
                         GcRecord::RootJniGlobal { .. } => {
@@ -291,31 +666,76 @@ impl ResultRecorder {
                             self.heap_dump_segments_gc_root_monitor_used += 1;
                         }
                         GcRecord::InstanceDump {
-                            class_object_id, ..
+                            object_id,
+                            stack_trace_serial_number,
+                            class_object_id,
+                            field_values,
+                            ..
                         } => {
-                            self.classes_all_instance_total_size_by_id
-                                .entry(*class_object_id)
-                                .or_insert_with(ClassInstanceCounter::empty)
-                                .add_instance();
+                            if let Some(field_values) = field_values.take() {
+                                self.keep_android_instance(
+                                    *object_id,
+                                    *class_object_id,
+                                    *field_values,
+                                );
+                            }
+                            if let Some(thread_serial_number) =
+                                self.thread_serial_number_by_object_id.get(object_id)
+                                && let Some(records) = self
+                                    .thread_records_by_serial_number
+                                    .get_mut(thread_serial_number)
+                            {
+                                records.object_in_heap = true;
+                            }
+                            if self.reports.class_loaders
+                                && let Some(loader_class_id) =
+                                    self.class_loader_class_by_id.get_mut(object_id)
+                            {
+                                *loader_class_id = *class_object_id;
+                            }
+                            self.index_object(
+                                *object_id,
+                                ObjectShape::Instance {
+                                    class_object_id: *class_object_id,
+                                },
+                            );
+                            for counters in self.current_counters(*stack_trace_serial_number) {
+                                counters.add_instance(*class_object_id);
+                            }
 
                             self.heap_dump_segments_gc_instance_dump += 1;
                         }
                         GcRecord::ObjectArrayDump {
+                            object_id,
+                            stack_trace_serial_number,
                             number_of_elements,
                             array_class_id,
                             ..
                         } => {
                             let size_bytes = object_array_size(self.id_size, *number_of_elements);
-                            self.object_array_counters
-                                .entry(*array_class_id)
-                                .or_insert_with(ArrayCounter::empty)
-                                .add_array(size_bytes);
+                            self.index_object(
+                                *object_id,
+                                ObjectShape::ObjectArray {
+                                    array_class_id: *array_class_id,
+                                    number_of_elements: *number_of_elements,
+                                },
+                            );
+                            for counters in self.current_counters(*stack_trace_serial_number) {
+                                counters.add_object_array(
+                                    *array_class_id,
+                                    *number_of_elements,
+                                    size_bytes,
+                                );
+                            }
 
                             self.heap_dump_segments_gc_object_array_dump += 1;
                         }
                         GcRecord::PrimitiveArrayDump {
+                            object_id,
+                            stack_trace_serial_number,
                             number_of_elements,
                             element_type,
+                            zero_elements,
                             ..
                         } => {
                             let size_bytes = primitive_array_size(
@@ -323,49 +743,102 @@ impl ResultRecorder {
                                 *element_type,
                                 *number_of_elements,
                             );
-                            self.primitive_array_counters
-                                .entry(*element_type)
-                                .or_insert_with(ArrayCounter::empty)
-                                .add_array(size_bytes);
+                            self.index_object(
+                                *object_id,
+                                ObjectShape::PrimitiveArray {
+                                    element_type: *element_type,
+                                    number_of_elements: *number_of_elements,
+                                },
+                            );
+                            for counters in self.current_counters(*stack_trace_serial_number) {
+                                counters.add_primitive_array(
+                                    *element_type,
+                                    *number_of_elements,
+                                    size_bytes,
+                                );
+                            }
+                            if let Some(zero_arrays) = &mut self.zero_arrays {
+                                zero_arrays.add_array(ZeroArray {
+                                    size_bytes,
+                                    object_id: *object_id,
+                                    number_of_elements: *number_of_elements,
+                                    zero_elements: *zero_elements,
+                                    element_type: *element_type,
+                                });
+                            }
 
                             self.heap_dump_segments_gc_primitive_array_dump += 1;
                         }
                         GcRecord::ClassDump(class_dump_fields) => {
                             let class_object_id = class_dump_fields.class_object_id;
+                            let class_loader_object_id = class_dump_fields.class_loader_object_id;
+                            if self.reports.class_loaders && class_loader_object_id != 0 {
+                                self.class_loader_by_class_id
+                                    .insert(class_object_id, class_loader_object_id);
+                                self.class_loader_class_by_id
+                                    .entry(class_loader_object_id)
+                                    .or_insert(0);
+                            }
+                            self.index_object(class_object_id, ObjectShape::Class);
                             self.classes_single_instance_size_by_id
                                 .entry(class_object_id)
                                 .or_insert_with(|| {
                                     let super_class_object_id =
                                         class_dump_fields.super_class_object_id;
-                                    let instance_field_types =
-                                        mem::take(&mut class_dump_fields.instance_fields)
-                                            .into_iter()
-                                            .map(|field| field.field_type)
-                                            .collect();
-                                    ClassInfo::new(super_class_object_id, instance_field_types)
+                                    let instance_fields =
+                                        mem::take(&mut class_dump_fields.instance_fields);
+                                    ClassInfo::new(super_class_object_id, instance_fields)
                                 });
 
                             self.heap_dump_segments_gc_class_dump += 1;
                         }
                         // Android HPROF 1.0.3 extension records. They are
-                        // parsed for stream alignment and root tracking; the
-                        // summary does not surface per-extension counts.
-                        GcRecord::RootInternedString { .. }
-                        | GcRecord::RootFinalizing { .. }
-                        | GcRecord::RootDebugger { .. }
-                        | GcRecord::RootReferenceCleanup { .. }
-                        | GcRecord::RootVmInternal { .. }
-                        | GcRecord::RootJniMonitor { .. }
-                        | GcRecord::Unreachable { .. }
-                        | GcRecord::HeapDumpInfo { .. } => {}
+                        // parsed for stream alignment and root tracking; only
+                        // the JSON record counts surface them.
+                        GcRecord::RootInternedString { .. } => {
+                            self.heap_dump_segments_gc_root_interned_string += 1;
+                        }
+                        GcRecord::RootFinalizing { .. } => {
+                            self.heap_dump_segments_gc_root_finalizing += 1;
+                        }
+                        GcRecord::RootDebugger { .. } => {
+                            self.heap_dump_segments_gc_root_debugger += 1;
+                        }
+                        GcRecord::RootReferenceCleanup { .. } => {
+                            self.heap_dump_segments_gc_root_reference_cleanup += 1;
+                        }
+                        GcRecord::RootVmInternal { .. } => {
+                            self.heap_dump_segments_gc_root_vm_internal += 1;
+                        }
+                        GcRecord::RootJniMonitor { .. } => {
+                            self.heap_dump_segments_gc_root_jni_monitor += 1;
+                        }
+                        GcRecord::Unreachable { .. } => {
+                            self.heap_dump_segments_gc_unreachable += 1;
+                        }
+                        GcRecord::HeapDumpInfo {
+                            heap_type,
+                            heap_name_id,
+                        } => self.switch_heap(*heap_type, *heap_name_id),
                         // The body was suppressed by the dumper (e.g.
                         // zygote-shared arrays), so the bytes are not attributed
                         // to this dump; count it but with zero size.
-                        GcRecord::PrimitiveArrayNoDataDump { element_type, .. } => {
-                            self.primitive_array_counters
-                                .entry(*element_type)
-                                .or_insert_with(ArrayCounter::empty)
-                                .add_array(0);
+                        GcRecord::PrimitiveArrayNoDataDump {
+                            object_id,
+                            stack_trace_serial_number,
+                            number_of_elements,
+                            element_type,
+                            ..
+                        } => {
+                            self.index_object(
+                                *object_id,
+                                ObjectShape::PrimitiveArrayNoData {
+                                    element_type: *element_type,
+                                },
+                            );
+                            for counters in self.current_counters(*stack_trace_serial_number) {
+                                counters.add_primitive_array(*element_type, *number_of_elements, 0);
+                            }
                             self.heap_dump_segments_gc_primitive_array_dump += 1;
                         }
                     }
@@ -374,45 +847,423 @@ impl ResultRecorder {
         }
     }
 
-    fn render_captured_strings(&self) -> String {
-        let mut strings: Vec<_> = self.utf8_strings_by_id.values().collect();
-        strings.sort_unstable();
-        let mut result = String::from("\nList of Strings\n");
-        for s in strings {
-            result.push_str(s);
-            result.push('\n');
+    // Counters of the current heap, and of the current dump and of the
+    // allocating stack trace if tracked.
+    fn current_counters(
+        &mut self,
+        stack_trace_serial_number: u32,
+    ) -> impl Iterator<Item = &mut HeapCounters> {
+        let alloc_trace = (self.reports.alloc_traces || self.reports.alloc_collapsed).then(|| {
+            self.alloc_trace_counters
+                .entry(stack_trace_serial_number)
+                .or_insert_with(|| HeapCounters::new(DEFAULT_HEAP_TYPE, 0, false))
+        });
+        std::iter::once(&mut self.heaps[self.current_heap])
+            .chain(self.dumps.last_mut().map(|(_, counters)| counters))
+            .chain(alloc_trace)
+    }
+
+    fn alloc_trace_report(&self, missing_class_ids: &mut AHashSet<u64>) -> AllocationTraceReport {
+        let traces = self
+            .alloc_trace_counters
+            .iter()
+            .map(|(serial_number, counters)| AllocationTrace {
+                serial_number: *serial_number,
+                frames: self
+                    .stack_trace_by_serial_number
+                    .get(serial_number)
+                    .map(|stack| {
+                        stack
+                            .stack_frame_ids
+                            .iter()
+                            .map(|id| self.render_stack_frame(*id, missing_class_ids))
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+                memory_usage: self.aggregate_memory_usage(counters, missing_class_ids),
+            })
+            .collect();
+        AllocationTraceReport::new(traces)
+    }
+
+    // Epoch milliseconds of a record timestamp, those are relative to the
+    // file header's one.
+    fn record_time(&self, timestamp_micros: u32) -> Option<u64> {
+        (self.timestamp != 0).then(|| self.timestamp + u64::from(timestamp_micros) / 1000)
+    }
+
+    fn dump_report(&self, missing_class_ids: &mut AHashSet<u64>) -> DumpReport {
+        let dumps = self
+            .dumps
+            .iter()
+            .map(|(timestamp_micros, counters)| DumpUsage {
+                timestamp: self.record_time(*timestamp_micros),
+                memory_usage: self.aggregate_memory_usage(counters, missing_class_ids),
+            })
+            .collect();
+        DumpReport {
+            dumps,
+            diff_with_previous: self.reports.diff_dumps,
+        }
+    }
+
+    // The following objects belong to this heap until the next heap info.
+    fn switch_heap(&mut self, heap_type: u32, heap_name_id: u64) {
+        self.current_heap = match self.heaps.iter().position(|h| h.heap_type == heap_type) {
+            Some(index) => index,
+            None => {
+                self.heaps.push(HeapCounters::new(
+                    heap_type,
+                    heap_name_id,
+                    self.reports.array_sizes,
+                ));
+                self.heaps.len() - 1
+            }
+        };
+    }
+
+    fn is_counted_heap(&self, heap: &HeapCounters) -> bool {
+        !(self.reports.exclude_shared_heaps && is_shared_heap(heap.heap_type))
+    }
+
+    // Counters of all the heaps included in the totals.
+    fn counted_heaps(&self) -> HeapCounters {
+        let mut counted = HeapCounters::new(DEFAULT_HEAP_TYPE, 0, self.reports.array_sizes);
+        for heap in self.heaps.iter().filter(|h| self.is_counted_heap(h)) {
+            counted.merge(heap);
+        }
+        counted
+    }
+
+    // Counters of every heap, the excluded ones included.
+    fn all_heaps(&self) -> HeapCounters {
+        let mut all = HeapCounters::new(DEFAULT_HEAP_TYPE, 0, false);
+        for heap in &self.heaps {
+            all.merge(heap);
+        }
+        all
+    }
+
+    // Histogram of every non empty heap, in order of appearance.
+    fn heap_usages(&self, missing_class_ids: &mut AHashSet<u64>) -> Vec<HeapUsage> {
+        self.heaps
+            .iter()
+            .filter(|heap| !heap.is_empty())
+            .map(|heap| HeapUsage {
+                name: self
+                    .utf8_strings_by_id
+                    .get(&heap.heap_name_id)
+                    .map_or_else(|| default_heap_name(heap.heap_type), ToString::to_string),
+                shared: is_shared_heap(heap.heap_type),
+                excluded: !self.is_counted_heap(heap),
+                memory_usage: self.aggregate_memory_usage(heap, missing_class_ids),
+            })
+            .collect()
+    }
+
+    fn index_object(&mut self, object_id: u64, shape: ObjectShape) {
+        if self.index_objects {
+            self.object_shapes.insert(object_id, shape);
+        }
+    }
+
+    // Class name and shallow size of an indexed object, `None` if it is not
+    // in the dump.
+    fn describe_object(
+        &self,
+        object_id: u64,
+        missing_class_ids: &mut AHashSet<u64>,
+    ) -> Option<(String, u64)> {
+        let shape = self.object_shapes.get(&object_id)?;
+        let description = match *shape {
+            ObjectShape::Instance { class_object_id } => (
+                self.get_class_name_string(class_object_id, missing_class_ids),
+                self.calculate_instance_size(class_object_id, missing_class_ids),
+            ),
+            ObjectShape::ObjectArray {
+                array_class_id,
+                number_of_elements,
+            } => (
+                self.object_array_label(array_class_id, missing_class_ids),
+                object_array_size(self.id_size, number_of_elements),
+            ),
+            ObjectShape::PrimitiveArray {
+                element_type,
+                number_of_elements,
+            } => (
+                primitive_array_label(element_type),
+                primitive_array_size(self.id_size, element_type, number_of_elements),
+            ),
+            ObjectShape::PrimitiveArrayNoData { element_type } => {
+                (primitive_array_label(element_type), 0)
+            }
+            ObjectShape::Class => ("java.lang.Class".to_string(), 0),
+        };
+        Some(description)
+    }
+
+    // The tracked Android class `class_id` is or extends, `Some(None)` if none
+    // and `None` while a class of its hierarchy is not dumped yet.
+    fn android_class(&self, mut class_id: u64) -> Option<Option<AndroidClass>> {
+        while class_id != 0 {
+            if let Some(resolved) = self.android_class_by_id.get(&class_id) {
+                return Some(*resolved);
+            }
+            if let Some(android_class) = self.android_framework_classes.get(&class_id) {
+                return Some(Some(*android_class));
+            }
+            class_id = self
+                .classes_single_instance_size_by_id
+                .get(&class_id)?
+                .super_class_object_id;
+        }
+        Some(None)
+    }
+
+    // Only the candidates are kept: an instance of a resolved hierarchy is
+    // kept if it is a tracked class, an unresolved one if a tracked framework
+    // class is loaded at all, as its missing class dumps could extend it.
+    fn keep_android_instance(&mut self, object_id: u64, class_id: u64, field_values: Vec<u8>) {
+        let android_class = self.android_class(class_id);
+        if let Some(resolved) = android_class {
+            self.android_class_by_id.insert(class_id, resolved);
+        }
+        let is_candidate = match android_class {
+            Some(resolved) => resolved.is_some(),
+            None => !self.android_framework_classes.is_empty(),
+        };
+        if is_candidate {
+            self.android_instances
+                .push((object_id, class_id, field_values));
+        }
+    }
+
+    // Decoded instance fields by name, the fields of a class shadow the ones
+    // of its super classes.
+    fn instance_fields(&self, class_id: u64, field_values: &[u8]) -> AHashMap<&str, FieldValue> {
+        let mut fields = AHashMap::new();
+        let mut rest = field_values;
+        let mut class_id = class_id;
+        while let Some(class_info) = self.classes_single_instance_size_by_id.get(&class_id) {
+            for field in &class_info.instance_fields {
+                let Ok((next, value)) = parse_field_value(field.field_type, self.id_size)(rest)
+                else {
+                    return fields;
+                };
+                rest = next;
+                if let Some(name) = self.utf8_strings_by_id.get(&field.name_id) {
+                    fields.entry(&**name).or_insert(value);
+                }
+            }
+            class_id = class_info.super_class_object_id;
+        }
+        fields
+    }
+
+    fn android_report(&self, missing_class_ids: &mut AHashSet<u64>) -> AndroidReport {
+        let mut bitmaps = Vec::new();
+        let mut activities = Vec::new();
+        let mut fragments = Vec::new();
+        for (object_id, class_id, field_values) in &self.android_instances {
+            let Some(Some(android_class)) = self.android_class(*class_id) else {
+                continue;
+            };
+            let fields = self.instance_fields(*class_id, field_values);
+            let int_field = |name: &str| match fields.get(name) {
+                Some(FieldValue::Int(value)) => *value,
+                _ => 0,
+            };
+            let is_set = |name: &str| matches!(fields.get(name), Some(FieldValue::Bool(true)));
+            let class_name = self.get_class_name_string(*class_id, missing_class_ids);
+            let component = |leak_reasons| AndroidComponent {
+                object_id: *object_id,
+                class_name,
+                leak_reasons,
+                not_attached: false,
+            };
+            match android_class {
+                AndroidClass::Bitmap => bitmaps.push(Bitmap {
+                    object_id: *object_id,
+                    width: int_field("mWidth"),
+                    height: int_field("mHeight"),
+                    buffer_bytes: match fields.get("mBuffer") {
+                        Some(FieldValue::Object(buffer_id)) if *buffer_id != 0 => self
+                            .describe_object(*buffer_id, missing_class_ids)
+                            .map(|(_, size)| size),
+                        _ => None,
+                    },
+                }),
+                AndroidClass::Activity => {
+                    let leak_reasons = [("mDestroyed", "destroyed"), ("mFinished", "finished")]
+                        .into_iter()
+                        .filter(|(field, _)| is_set(field))
+                        .map(|(_, reason)| reason)
+                        .collect();
+                    activities.push(component(leak_reasons));
+                }
+                AndroidClass::Fragment => {
+                    // removed by a committed transaction but still referenced
+                    let leak_reasons = if is_set("mRemoving") {
+                        vec!["removed"]
+                    } else {
+                        vec![]
+                    };
+                    fragments.push(AndroidComponent {
+                        not_attached: matches!(
+                            fields.get("mFragmentManager"),
+                            Some(FieldValue::Object(0))
+                        ),
+                        ..component(leak_reasons)
+                    });
+                }
+            }
         }
-        result
+        AndroidReport::new(bitmaps, activities, fragments)
+    }
+
+    fn gc_root_report(&self, missing_class_ids: &mut AHashSet<u64>) -> GcRootReport {
+        GcRootReport::new(&self.gc_roots, |object_id| {
+            self.describe_object(object_id, missing_class_ids)
+        })
     }
 
-    fn render_duplicated_strings(&self) -> Option<String> {
-        let mut strings: Vec<_> = self.utf8_strings_by_id.values().collect();
+    fn captured_strings(&self) -> Vec<String> {
+        let mut strings: Vec<_> = self
+            .utf8_strings_by_id
+            .values()
+            .map(ToString::to_string)
+            .collect();
         strings.sort_unstable();
-        let all_len = strings.len();
-        strings.dedup();
-        let dedup_len = strings.len();
-        if all_len == dedup_len {
-            None
-        } else {
-            Some(format!(
-                "\nFound {} duplicated strings out of {} unique strings\n",
-                all_len - dedup_len,
-                all_len
-            ))
+        strings
+    }
+
+    fn duplicated_strings(&self) -> Option<DuplicatedStrings> {
+        let mut count_by_string: AHashMap<&str, u64> = AHashMap::new();
+        for s in self.utf8_strings_by_id.values() {
+            *count_by_string.entry(s).or_default() += 1;
         }
+        let string_count = self.utf8_strings_by_id.len() as u64;
+        let duplicated_count = string_count - count_by_string.len() as u64;
+        if duplicated_count == 0 {
+            return None;
+        }
+        let mut most_duplicated: Vec<_> = count_by_string
+            .into_iter()
+            .filter(|(_, count)| *count > 1)
+            .map(|(value, count)| DuplicatedString {
+                value: value.to_string(),
+                count,
+            })
+            .collect();
+        most_duplicated.sort_unstable_by(|a, b| b.count.cmp(&a.count).then(a.value.cmp(&b.value)));
+        most_duplicated.truncate(MOST_DUPLICATED_STRINGS);
+        Some(DuplicatedStrings {
+            duplicated_count,
+            string_count,
+            most_duplicated,
+        })
     }
 
-    fn render_thread_info(&self, missing_class_ids: &mut AHashSet<u64>) -> String {
-        let mut thread_info = String::new();
+    // Every thread of `thread_stack_traces` with its rendered frames.
+    fn thread_stacks(&self, missing_class_ids: &mut AHashSet<u64>) -> Vec<ThreadStack> {
+        self.thread_stack_traces()
+            .into_iter()
+            .enumerate()
+            .map(|(index, stack)| ThreadStack {
+                name: self.thread_name(index, stack),
+                thread_serial_number: stack.thread_serial_number,
+                stack_trace_serial_number: stack.serial_number,
+                frames: stack
+                    .stack_frame_ids
+                    .iter()
+                    .map(|stack_frame_id| {
+                        self.render_stack_frame(*stack_frame_id, missing_class_ids)
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
 
-        // for each stacktrace
+    // Non-empty stack traces sorted by serial number, the thread at index `i`
+    // is rendered as `Thread {i + 1}`.
+    fn thread_stack_traces(&self) -> Vec<&StackTraceData> {
         let mut stack_traces: Vec<_> = self
             .stack_trace_by_serial_number
             .iter()
             .filter(|(_, stack)| !stack.stack_frame_ids.is_empty()) // omit empty stacktraces
             .collect();
-
         stack_traces.sort_by_key(|(serial_number, _)| **serial_number);
+        stack_traces.into_iter().map(|(_, stack)| stack).collect()
+    }
+
+    // Name from the `StartThread` record if any, otherwise the position of
+    // the thread in `thread_stack_traces`.
+    fn thread_name(&self, index: usize, stack: &StackTraceData) -> String {
+        self.start_thread_name(stack.thread_serial_number)
+            .map_or_else(|| format!("Thread {}", index + 1), ToString::to_string)
+    }
+
+    fn start_thread_name(&self, thread_serial_number: u32) -> Option<&str> {
+        self.thread_name_id_by_serial_number
+            .get(&thread_serial_number)
+            .and_then(|name_id| self.utf8_strings_by_id.get(name_id))
+            .map(|name| &**name)
+    }
+
+    fn thread_lifecycle_report(&self) -> ThreadLifecycleReport {
+        // named like in the stack traces, a thread without one has no position
+        let stack_by_thread: AHashMap<u32, (usize, &StackTraceData)> = self
+            .thread_stack_traces()
+            .into_iter()
+            .enumerate()
+            .map(|(index, stack)| (stack.thread_serial_number, (index, stack)))
+            .collect();
+        let threads = self
+            .thread_records_by_serial_number
+            .iter()
+            .map(|(serial_number, records)| ThreadLifecycle {
+                serial_number: *serial_number,
+                object_id: records.object_id,
+                name: match stack_by_thread.get(serial_number) {
+                    Some((index, stack)) => self.thread_name(*index, stack),
+                    None => self
+                        .start_thread_name(*serial_number)
+                        .unwrap_or("unnamed thread")
+                        .to_string(),
+                },
+                group: self
+                    .utf8_strings_by_id
+                    .get(&records.group_name_id)
+                    .map(ToString::to_string),
+                started_at: records.started_at.and_then(|t| self.record_time(t)),
+                ended_at: records.ended_at.and_then(|t| self.record_time(t)),
+                alive: records.ended_at.is_none(),
+                object_in_heap: records.object_in_heap,
+            })
+            .collect();
+        ThreadLifecycleReport::new(threads)
+    }
+
+    fn render_thread_info(&self, missing_class_ids: &mut AHashSet<u64>) -> String {
+        let mut thread_info = String::new();
+
+        // for each stacktrace
+        let stack_traces = self.thread_stack_traces();
+
+        // (thread serial number, frame number) -> roots held by that frame
+        let mut locals_by_frame: AHashMap<(u32, u32), Vec<&GcRoot>> = AHashMap::new();
+        if self.reports.locals {
+            for root in &self.gc_roots {
+                if let (Some(thread_serial_number), Some(frame_number)) =
+                    (root.thread_serial_number, root.frame_number)
+                {
+                    locals_by_frame
+                        .entry((thread_serial_number, frame_number))
+                        .or_default()
+                        .push(root);
+                }
+            }
+        }
 
         writeln!(
             thread_info,
@@ -421,159 +1272,472 @@ impl ResultRecorder {
         )
         .expect("Could not write to thread info");
 
-        for (index, (_id, stack_data)) in stack_traces.iter().enumerate() {
-            write!(thread_info, "\nThread {}\n", index + 1)
+        for (index, stack_data) in stack_traces.iter().enumerate() {
+            write!(thread_info, "\n{}\n", self.thread_name(index, stack_data))
                 .expect("Could not write to thread info");
 
             //  for each stack frames
-            for stack_frame_id in &stack_data.stack_frame_ids {
-                // missing metadata is rendered as a placeholder instead of
-                // failing, like in `get_class_name_string`
-                let Some(stack_frame) = self.stack_frame_by_id.get(stack_frame_id) else {
-                    writeln!(
-                        thread_info,
-                        "  at <unknown stack frame 0x{stack_frame_id:x}>"
-                    )
-                    .expect("Could not write to thread info");
-                    continue;
-                };
-                let class_name = match self
-                    .class_data_by_serial_number
-                    .get(&stack_frame.class_serial_number)
-                    .and_then(|index| self.class_data.get(*index))
-                {
-                    Some(class_data) => {
-                        self.get_class_name_string(class_data.class_object_id, missing_class_ids)
-                    }
-                    None => format!(
-                        "<unknown class (serial {})>",
-                        stack_frame.class_serial_number
-                    ),
-                };
-                let method_name = self
-                    .utf8_strings_by_id
-                    .get(&stack_frame.method_name_id)
-                    .map_or("unknown method name", |b| &**b);
-                let file_name = self
-                    .utf8_strings_by_id
-                    .get(&stack_frame.source_file_name_id)
-                    .map_or("unknown source file", |b| &**b);
-
-                // >0: normal
-                // -1: unknown
-                // -2: compiled method
-                // -3: native method
-                let pretty_line_number = match stack_frame.line_number {
-                    -1 => "unknown line number".to_string(),
-                    -2 => "compiled method".to_string(),
-                    -3 => "native method".to_string(),
-                    number => format!("{number}"),
-                };
-
-                // pretty frame output
+            for (frame_number, stack_frame_id) in stack_data.stack_frame_ids.iter().enumerate() {
                 writeln!(
                     thread_info,
-                    "  at {class_name}.{method_name} ({file_name}:{pretty_line_number})"
+                    "  at {}",
+                    self.render_stack_frame(*stack_frame_id, missing_class_ids)
                 )
                 .expect("Could not write to thread info");
+
+                let frame_key = (
+                    stack_data.thread_serial_number,
+                    u32::try_from(frame_number).expect("frame number should fit in u32"),
+                );
+                for root in locals_by_frame.get(&frame_key).into_iter().flatten() {
+                    self.render_local(&mut thread_info, root, missing_class_ids);
+                }
             }
         }
         thread_info
     }
 
-    fn calculate_instance_size(&self, class_id: u64, missing_class_ids: &mut AHashSet<u64>) -> u64 {
-        u64::from(
-            self.calculate_instance_size_recursive(class_id, missing_class_ids)
-                .next_multiple_of(OBJECT_ALIGN),
-        )
+    fn stack_frame_class_name(
+        &self,
+        stack_frame: &StackFrameData,
+        missing_class_ids: &mut AHashSet<u64>,
+    ) -> String {
+        match self
+            .class_data_by_serial_number
+            .get(&stack_frame.class_serial_number)
+            .and_then(|index| self.class_data.get(*index))
+        {
+            Some(class_data) => {
+                self.get_class_name_string(class_data.class_object_id, missing_class_ids)
+            }
+            None => format!(
+                "<unknown class (serial {})>",
+                stack_frame.class_serial_number
+            ),
+        }
     }
 
-    fn calculate_instance_size_recursive(
+    // `Class.method(parameter types) (File.java:line)` of a stack frame, the
+    // parameters are omitted when the method signature is unknown
+    fn render_stack_frame(
         &self,
-        class_id: u64,
+        stack_frame_id: u64,
         missing_class_ids: &mut AHashSet<u64>,
-    ) -> u32 {
-        // A class id without a `ClassDump` record (see `get_class_name_string`)
-        // is sized as a bare object header so the analysis can keep going.
-        let Some(class_info) = self.classes_single_instance_size_by_id.get(&class_id) else {
-            missing_class_ids.insert(class_id);
-            return object_header_size(self.id_size);
+    ) -> String {
+        // missing metadata is rendered as a placeholder instead of
+        // failing, like in `get_class_name_string`
+        let Some(stack_frame) = self.stack_frame_by_id.get(&stack_frame_id) else {
+            return format!("<unknown stack frame 0x{stack_frame_id:x}>");
+        };
+        let class_name = self.stack_frame_class_name(stack_frame, missing_class_ids);
+        let method_name = self
+            .utf8_strings_by_id
+            .get(&stack_frame.method_name_id)
+            .map_or("unknown method name", |b| &**b);
+        let file_name = self
+            .utf8_strings_by_id
+            .get(&stack_frame.source_file_name_id)
+            .map_or("unknown source file", |b| &**b);
+
+        // >0: normal
+        // -1: unknown
+        // -2: compiled method
+        // -3: native method
+        let pretty_line_number = match stack_frame.line_number {
+            -1 => "unknown line number".to_string(),
+            -2 => "compiled method".to_string(),
+            -3 => "native method".to_string(),
+            number => format!("{number}"),
         };
 
-        if class_info.super_class_object_id == 0 {
-            return object_header_size(self.id_size);
-        }
+        let parameters = self
+            .utf8_strings_by_id
+            .get(&stack_frame.method_signature_id)
+            .and_then(|signature| decode_method_parameters(signature))
+            .map(|parameters| format!("({})", parameters.join(", ")))
+            .unwrap_or_default();
 
-        let fields_size = class_info
-            .instance_field_types
+        format!("{class_name}.{method_name}{parameters} ({file_name}:{pretty_line_number})")
+    }
+
+    fn is_object_wait_frame(&self, stack_frame_id: u64) -> bool {
+        let Some(stack_frame) = self.stack_frame_by_id.get(&stack_frame_id) else {
+            return false;
+        };
+        let class_name = self
+            .class_data_by_serial_number
+            .get(&stack_frame.class_serial_number)
+            .and_then(|index| self.class_data.get(*index))
+            .and_then(|class_data| self.utf8_strings_by_id.get(&class_data.class_name_id));
+        let method_name = self.utf8_strings_by_id.get(&stack_frame.method_name_id);
+        class_name.is_some_and(|name| &**name == "java/lang/Object")
+            && method_name.is_some_and(|name| &**name == "wait")
+    }
+
+    // Monitors held by stack frames, as (thread serial number, frame number,
+    // object id).
+    fn monitor_locals(&self) -> Vec<(u32, u32, u64)> {
+        let monitor_ids: AHashSet<u64> = self
+            .gc_roots
             .iter()
-            .map(|field_type| field_size(*field_type, self.id_size))
-            .sum::<u32>();
-        (fields_size
-            + self.calculate_instance_size_recursive(
-                class_info.super_class_object_id,
-                missing_class_ids,
-            ))
-        .next_multiple_of(self.id_size)
+            .filter(|root| is_monitor_root(root.kind))
+            .map(|root| root.object_id)
+            .collect();
+        self.gc_roots
+            .iter()
+            .filter(|root| monitor_ids.contains(&root.object_id))
+            .filter_map(|root| {
+                Some((
+                    root.thread_serial_number?,
+                    root.frame_number?,
+                    root.object_id,
+                ))
+            })
+            .collect()
     }
 
-    fn aggregate_memory_usage(
+    // A thread in `Object.wait` waits on the monitor of its topmost frame
+    // holding one, e.g. the `Thread.join` frame right below a native wait.
+    fn waiting_frame_by_thread(
         &self,
-        missing_class_ids: &mut AHashSet<u64>,
-    ) -> Vec<ClassAllocationStats> {
-        let mut classes_dump_vec: Vec<_> = self
-            .classes_all_instance_total_size_by_id
-            .iter()
-            .map(|(class_id, v)| {
-                let class_name = self.get_class_name_string(*class_id, missing_class_ids);
-                let size = self.calculate_instance_size(*class_id, missing_class_ids);
-                let total_size = size * v.number_of_instances;
-                ClassAllocationStats::new(
-                    class_name,
-                    v.number_of_instances,
-                    size, // all instances have the same size
-                    total_size,
-                )
+        monitor_locals: &[(u32, u32, u64)],
+        stack_by_thread: &AHashMap<u32, &StackTraceData>,
+    ) -> AHashMap<u32, u32> {
+        let mut waiting_frame_by_thread: AHashMap<u32, u32> = AHashMap::new();
+        for (thread_serial_number, frame_number, _) in monitor_locals {
+            let is_waiting = stack_by_thread
+                .get(thread_serial_number)
+                .and_then(|stack| stack.stack_frame_ids.first())
+                .is_some_and(|top_frame_id| self.is_object_wait_frame(*top_frame_id));
+            if is_waiting {
+                waiting_frame_by_thread
+                    .entry(*thread_serial_number)
+                    .and_modify(|waiting_frame| {
+                        *waiting_frame = (*waiting_frame).min(*frame_number)
+                    })
+                    .or_insert(*frame_number);
+            }
+        }
+        waiting_frame_by_thread
+    }
+
+    fn monitor_report(&self, missing_class_ids: &mut AHashSet<u64>) -> MonitorReport {
+        let mut monitors: AHashMap<u64, Monitor> = AHashMap::new();
+        for root in &self.gc_roots {
+            if is_monitor_root(root.kind) {
+                monitors
+                    .entry(root.object_id)
+                    .or_insert_with(|| {
+                        let class_name = self
+                            .describe_object(root.object_id, missing_class_ids)
+                            .map(|(class_name, _)| class_name);
+                        Monitor::new(root.object_id, class_name)
+                    })
+                    .root_kinds
+                    .push(root.kind);
+            }
+        }
+
+        let threads_by_serial_number: AHashMap<u32, (String, &StackTraceData)> = self
+            .thread_stack_traces()
+            .into_iter()
+            .enumerate()
+            .map(|(index, stack)| {
+                let name = self.thread_name(index, stack);
+                (stack.thread_serial_number, (name, stack))
+            })
+            .collect();
+        let stack_by_thread = threads_by_serial_number
+            .iter()
+            .map(|(thread_serial_number, (_, stack))| (*thread_serial_number, *stack))
+            .collect();
+
+        let monitor_locals = self.monitor_locals();
+        let waiting_frame_by_thread =
+            self.waiting_frame_by_thread(&monitor_locals, &stack_by_thread);
+
+        for (thread_serial_number, frame_number, object_id) in monitor_locals {
+            let Some((thread, stack)) = threads_by_serial_number.get(&thread_serial_number) else {
+                continue;
+            };
+            let Some(stack_frame_id) = stack.stack_frame_ids.get(frame_number as usize) else {
+                continue;
+            };
+            let reference = MonitorReference {
+                thread: thread.clone(),
+                frame: self.render_stack_frame(*stack_frame_id, missing_class_ids),
+            };
+            let monitor = monitors
+                .get_mut(&object_id)
+                .expect("monitor locals are filtered on known monitors");
+            if waiting_frame_by_thread.get(&thread_serial_number) == Some(&frame_number) {
+                monitor.waiting.push(reference);
+            } else {
+                monitor.referencing.push(reference);
+            }
+        }
+
+        MonitorReport::new(monitors.into_values().collect())
+    }
+
+    fn jstack_frame(
+        &self,
+        stack_frame_id: u64,
+        missing_class_ids: &mut AHashSet<u64>,
+    ) -> JstackFrame {
+        let Some(stack_frame) = self.stack_frame_by_id.get(&stack_frame_id) else {
+            return JstackFrame::Unknown { stack_frame_id };
+        };
+        JstackFrame::Known {
+            class_name: self.stack_frame_class_name(stack_frame, missing_class_ids),
+            method_name: self
+                .utf8_strings_by_id
+                .get(&stack_frame.method_name_id)
+                .map_or_else(|| "unknown method name".to_string(), ToString::to_string),
+            file_name: self
+                .utf8_strings_by_id
+                .get(&stack_frame.source_file_name_id)
+                .map(ToString::to_string),
+            line_number: stack_frame.line_number,
+        }
+    }
+
+    fn jstack_threads(&self, missing_class_ids: &mut AHashSet<u64>) -> Vec<JstackThread> {
+        let stacks = self.thread_stack_traces();
+        let stack_by_thread = stacks
+            .iter()
+            .map(|stack| (stack.thread_serial_number, *stack))
+            .collect();
+        let monitor_locals = self.monitor_locals();
+        let waiting_frame_by_thread =
+            self.waiting_frame_by_thread(&monitor_locals, &stack_by_thread);
+
+        let mut threads = Vec::with_capacity(stacks.len());
+        for (index, stack) in stacks.iter().enumerate() {
+            let thread_serial_number = stack.thread_serial_number;
+            let mut monitor = |object_id| JstackMonitor {
+                object_id,
+                class_name: self
+                    .describe_object(object_id, missing_class_ids)
+                    .map(|(class_name, _)| class_name),
+            };
+            let waiting_on = waiting_frame_by_thread
+                .get(&thread_serial_number)
+                .and_then(|waiting_frame| {
+                    monitor_locals
+                        .iter()
+                        .find(|(t, f, _)| *t == thread_serial_number && f == waiting_frame)
+                })
+                .map(|(_, _, object_id)| monitor(*object_id));
+            // every monitor held by the thread was locked by the topmost
+            // frame holding it besides `Object.wait` itself, a waited monitor
+            // included as waiting on it requires owning it
+            let mut lock_frame_by_monitor: AHashMap<u64, usize> = AHashMap::new();
+            for (_, frame_number, object_id) in monitor_locals
+                .iter()
+                .filter(|(t, _, _)| *t == thread_serial_number)
+            {
+                let frame_number = *frame_number as usize;
+                let is_lock_frame = stack
+                    .stack_frame_ids
+                    .get(frame_number)
+                    .is_some_and(|id| !self.is_object_wait_frame(*id));
+                if is_lock_frame {
+                    lock_frame_by_monitor
+                        .entry(*object_id)
+                        .and_modify(|lock_frame| *lock_frame = (*lock_frame).min(frame_number))
+                        .or_insert(frame_number);
+                }
+            }
+            let mut lock_frames: Vec<_> = lock_frame_by_monitor.into_iter().collect();
+            lock_frames
+                .sort_unstable_by_key(|(object_id, frame_number)| (*frame_number, *object_id));
+            let locked = lock_frames
+                .into_iter()
+                .map(|(object_id, frame_number)| (frame_number, monitor(object_id)))
+                .collect();
+            let frames = stack
+                .stack_frame_ids
+                .iter()
+                .map(|stack_frame_id| self.jstack_frame(*stack_frame_id, missing_class_ids))
+                .collect();
+            threads.push(JstackThread {
+                name: self.thread_name(index, stack),
+                thread_serial_number,
+                thread_object_id: self
+                    .thread_object_id_by_serial_number
+                    .get(&thread_serial_number)
+                    .copied(),
+                frames,
+                waiting_on,
+                locked,
+            });
+        }
+        threads
+    }
+
+    // jstack style line for an object held by a stack frame
+    fn render_local(
+        &self,
+        thread_info: &mut String,
+        root: &GcRoot,
+        missing_class_ids: &mut AHashSet<u64>,
+    ) {
+        let kind = match root.kind {
+            RootKind::JniLocal => "JNI local",
+            RootKind::JniMonitor => "JNI monitor",
+            _ => "local",
+        };
+        let object = match self.describe_object(root.object_id, missing_class_ids) {
+            Some((class_name, size_bytes)) => {
+                format!("a {class_name}, {}", pretty_bytes_size(size_bytes))
+            }
+            None => "not in the dump".to_string(),
+        };
+        writeln!(
+            thread_info,
+            "      - {kind} <0x{:x}> ({object})",
+            root.object_id
+        )
+        .expect("Could not write to thread info");
+    }
+
+    fn calculate_instance_size(&self, class_id: u64, missing_class_ids: &mut AHashSet<u64>) -> u64 {
+        u64::from(
+            self.calculate_instance_size_recursive(class_id, missing_class_ids)
+                .next_multiple_of(OBJECT_ALIGN),
+        )
+    }
+
+    fn calculate_instance_size_recursive(
+        &self,
+        class_id: u64,
+        missing_class_ids: &mut AHashSet<u64>,
+    ) -> u32 {
+        // A class id without a `ClassDump` record (see `get_class_name_string`)
+        // is sized as a bare object header so the analysis can keep going.
+        let Some(class_info) = self.classes_single_instance_size_by_id.get(&class_id) else {
+            missing_class_ids.insert(class_id);
+            return object_header_size(self.id_size);
+        };
+
+        if class_info.super_class_object_id == 0 {
+            return object_header_size(self.id_size);
+        }
+
+        let fields_size = class_info
+            .instance_fields
+            .iter()
+            .map(|field| field_size(field.field_type, self.id_size))
+            .sum::<u32>();
+        (fields_size
+            + self.calculate_instance_size_recursive(
+                class_info.super_class_object_id,
+                missing_class_ids,
+            ))
+        .next_multiple_of(self.id_size)
+    }
+
+    fn object_array_label(
+        &self,
+        array_class_id: u64,
+        missing_class_ids: &mut AHashSet<u64>,
+    ) -> String {
+        let class_name = self.get_class_name_string(array_class_id, missing_class_ids);
+        // array class names are decoded, only placeholders lack the suffix
+        if class_name.ends_with("[]") {
+            class_name
+        } else {
+            format!("{class_name}[]")
+        }
+    }
+
+    // Length distribution per array type, sorted by total size. Object arrays
+    // of the same class loaded by several classloaders are merged.
+    fn aggregate_array_distribution(
+        &self,
+        heap: &HeapCounters,
+        missing_class_ids: &mut AHashSet<u64>,
+    ) -> Vec<ArrayDistribution> {
+        let id_size = self.id_size;
+        let mut distributions: Vec<_> = heap
+            .primitive_array_counters
+            .iter()
+            .filter_map(|(field_type, ac)| {
+                let histogram = ac.length_histogram.as_deref()?;
+                Some(
+                    histogram.distribution(primitive_array_label(*field_type), |n| {
+                        primitive_array_size(id_size, *field_type, n)
+                    }),
+                )
+            })
+            .collect();
+
+        let mut object_histograms: AHashMap<String, LengthHistogram> = AHashMap::new();
+        for (class_id, ac) in &heap.object_array_counters {
+            if let Some(histogram) = &ac.length_histogram {
+                object_histograms
+                    .entry(self.object_array_label(*class_id, missing_class_ids))
+                    .or_insert_with(LengthHistogram::empty)
+                    .merge(histogram);
+            }
+        }
+        distributions.extend(object_histograms.into_iter().map(|(label, histogram)| {
+            histogram.distribution(label, |n| object_array_size(id_size, n))
+        }));
+
+        distributions.sort_unstable_by(|a, b| {
+            b.total_size_bytes
+                .cmp(&a.total_size_bytes)
+                .then_with(|| a.array_type.cmp(&b.array_type))
+        });
+        distributions
+    }
+
+    fn aggregate_memory_usage(
+        &self,
+        heap: &HeapCounters,
+        missing_class_ids: &mut AHashSet<u64>,
+    ) -> Vec<ClassAllocationStats> {
+        let mut classes_dump_vec: Vec<_> = heap
+            .classes_all_instance_total_size_by_id
+            .iter()
+            .map(|(class_id, v)| {
+                let class_name = self.get_class_name_string(*class_id, missing_class_ids);
+                let size = self.calculate_instance_size(*class_id, missing_class_ids);
+                let total_size = size * v.number_of_instances;
+                ClassAllocationStats::new(
+                    class_name,
+                    v.number_of_instances,
+                    size, // all instances have the same size
+                    total_size,
+                )
+                .with_class_loader(self.class_loader_label(*class_id, missing_class_ids))
             })
             .collect();
 
         let array_primitives_dump_vec =
-            self.primitive_array_counters
+            heap.primitive_array_counters
                 .iter()
                 .map(|(field_type, ac)| {
-                    let primitive_type = format!("{field_type:?}").to_lowercase();
-                    let primitive_array_label = format!("{primitive_type}[]");
-
                     ClassAllocationStats::new(
-                        primitive_array_label,
+                        primitive_array_label(*field_type),
                         ac.number_of_arrays,
                         ac.max_size_bytes_seen,
                         ac.total_size_bytes,
                     )
+                    .with_class_loader(self.reports.class_loaders.then(|| "bootstrap".to_string()))
                 });
 
         // For array of objects we are interested in the total size of the array headers and outgoing elements references
-        let array_objects_dump_vec = self.object_array_counters.iter().map(|(class_id, ac)| {
-            let raw_class_name = self.get_class_name_string(*class_id, missing_class_ids);
-            let cleaned_class_name: String = if raw_class_name.starts_with("[[L") {
-                // remove '[[L' prefix and ';' suffix
-                raw_class_name[3..raw_class_name.len() - 1].to_string()
-            } else if raw_class_name.starts_with("[L") {
-                // remove '[L' prefix and ';' suffix
-                raw_class_name[2..raw_class_name.len() - 1].to_string()
-            } else {
-                // TODO: what are those ([[C, [[D, [[B, [[S ...)? boxed primitives are already present
-                raw_class_name
-            };
-
-            let object_array_label = format!("{cleaned_class_name}[]");
-
+        let array_objects_dump_vec = heap.object_array_counters.iter().map(|(class_id, ac)| {
             ClassAllocationStats::new(
-                object_array_label,
+                self.object_array_label(*class_id, missing_class_ids),
                 ac.number_of_arrays,
                 ac.max_size_bytes_seen,
                 ac.total_size_bytes,
             )
+            .with_class_loader(self.class_loader_label(*class_id, missing_class_ids))
         });
 
         // Merge results
@@ -588,11 +1752,77 @@ impl ResultRecorder {
                 .then(b.allocation_size_bytes.cmp(&a.allocation_size_bytes))
                 .then(b.instance_count.cmp(&a.instance_count))
                 .then(b.largest_allocation_bytes.cmp(&a.largest_allocation_bytes))
+                .then_with(|| b.class_loader.cmp(&a.class_loader))
         });
         classes_dump_vec
     }
 
-    pub fn render_summary(&self) -> String {
+    // Class and object id of the loader of a class, `None` without
+    // `--classloader-column`.
+    fn class_loader_label(
+        &self,
+        class_id: u64,
+        missing_class_ids: &mut AHashSet<u64>,
+    ) -> Option<String> {
+        if !self.reports.class_loaders {
+            return None;
+        }
+        let label = match self.class_loader_by_class_id.get(&class_id) {
+            None => "bootstrap".to_string(),
+            Some(loader_id) => match self.class_loader_class_by_id.get(loader_id) {
+                Some(loader_class_id) if *loader_class_id != 0 => format!(
+                    "{}@0x{loader_id:x}",
+                    self.get_class_name_string(*loader_class_id, missing_class_ids)
+                ),
+                _ => format!("0x{loader_id:x}"),
+            },
+        };
+        Some(label)
+    }
+
+    fn record_counts(&self) -> RecordCounts {
+        RecordCounts {
+            utf8_strings: self.utf8_strings_by_id.len() as u64,
+            classes_loaded: self.class_data_by_id.len() as u64,
+            classes_unloaded: self.classes_unloaded,
+            stack_traces: self.stack_traces,
+            stack_frames: self.stack_frames,
+            start_threads: self.start_threads,
+            allocation_sites: self.allocation_sites,
+            end_threads: self.end_threads,
+            control_settings: self.control_settings,
+            cpu_samples: self.cpu_samples,
+            heap_summaries: self.heap_summaries,
+            heap_dumps: self.heap_dumps,
+            heap_dump_sub_records: self.heap_dump_segments_all_sub_records,
+            gc_roots: GcRootCounts {
+                unknown: self.heap_dump_segments_gc_root_unknown,
+                thread_object: self.heap_dump_segments_gc_root_thread_object,
+                jni_global: self.heap_dump_segments_gc_root_jni_global,
+                jni_local: self.heap_dump_segments_gc_root_jni_local,
+                java_frame: self.heap_dump_segments_gc_root_java_frame,
+                native_stack: self.heap_dump_segments_gc_root_native_stack,
+                sticky_class: self.heap_dump_segments_gc_root_sticky_class,
+                thread_block: self.heap_dump_segments_gc_root_thread_block,
+                monitor_used: self.heap_dump_segments_gc_root_monitor_used,
+                interned_string: self.heap_dump_segments_gc_root_interned_string,
+                finalizing: self.heap_dump_segments_gc_root_finalizing,
+                debugger: self.heap_dump_segments_gc_root_debugger,
+                reference_cleanup: self.heap_dump_segments_gc_root_reference_cleanup,
+                vm_internal: self.heap_dump_segments_gc_root_vm_internal,
+                jni_monitor: self.heap_dump_segments_gc_root_jni_monitor,
+                unreachable: self.heap_dump_segments_gc_unreachable,
+            },
+            primitive_array_dumps: self.heap_dump_segments_gc_primitive_array_dump,
+            object_array_dumps: self.heap_dump_segments_gc_object_array_dump,
+            class_dumps: self.heap_dump_segments_gc_class_dump,
+            instance_dumps: self.heap_dump_segments_gc_instance_dump,
+        }
+    }
+
+    // `memory_usage` holds the computed shallow totals the heap summary is
+    // compared to, a sanity check of the object size model.
+    pub fn render_summary(&self, memory_usage: &[ClassAllocationStats]) -> String {
         let capture_time = if self.timestamp == 0 {
             String::new()
         } else {
@@ -601,6 +1831,7 @@ impl ResultRecorder {
                 pretty_timestamp_utc(self.timestamp)
             )
         };
+        let counts = self.record_counts();
         let top_summary = formatdoc!(
             "\nFile content summary:\n
             UTF-8 Strings: {}
@@ -611,22 +1842,26 @@ impl ResultRecorder {
             Start threads: {}
             Allocation sites: {}
             End threads: {}
-            Control settings: {}
+            Control settings: {}{}
             CPU samples: {}",
-            self.utf8_strings_by_id.len(),
-            self.class_data_by_id.len(),
-            self.classes_unloaded,
-            self.stack_traces,
-            self.stack_frames,
-            self.start_threads,
-            self.allocation_sites,
-            self.end_threads,
-            self.control_settings,
-            self.cpu_samples
+            counts.utf8_strings,
+            counts.classes_loaded,
+            counts.classes_unloaded,
+            counts.stack_traces,
+            counts.stack_frames,
+            counts.start_threads,
+            counts.allocation_sites,
+            counts.end_threads,
+            counts.control_settings,
+            self.control_settings_info
+                .map(render_control_settings)
+                .unwrap_or_default(),
+            counts.cpu_samples
         );
 
+        let gc_roots = &counts.gc_roots;
         let heap_summary = formatdoc!(
-            "Heap summaries: {}
+            "Heap summaries: {}{}
             {} heap dumps containing in total {} segments:
             ..GC root unknown: {}
             ..GC root thread objects: {}
@@ -641,30 +1876,69 @@ impl ResultRecorder {
             ..GC object array dump: {}
             ..GC class dump: {}
             ..GC instance dump: {}",
-            self.heap_summaries,
-            self.heap_dumps,
-            self.heap_dump_segments_all_sub_records,
-            self.heap_dump_segments_gc_root_unknown,
-            self.heap_dump_segments_gc_root_thread_object,
-            self.heap_dump_segments_gc_root_jni_global,
-            self.heap_dump_segments_gc_root_jni_local,
-            self.heap_dump_segments_gc_root_java_frame,
-            self.heap_dump_segments_gc_root_native_stack,
-            self.heap_dump_segments_gc_root_sticky_class,
-            self.heap_dump_segments_gc_root_thread_block,
-            self.heap_dump_segments_gc_root_monitor_used,
-            self.heap_dump_segments_gc_primitive_array_dump,
-            self.heap_dump_segments_gc_object_array_dump,
-            self.heap_dump_segments_gc_class_dump,
-            self.heap_dump_segments_gc_instance_dump,
+            counts.heap_summaries,
+            self.heap_summary
+                .map(|summary| render_heap_summary(summary, memory_usage))
+                .unwrap_or_default(),
+            counts.heap_dumps,
+            counts.heap_dump_sub_records,
+            gc_roots.unknown,
+            gc_roots.thread_object,
+            gc_roots.jni_global,
+            gc_roots.jni_local,
+            gc_roots.java_frame,
+            gc_roots.native_stack,
+            gc_roots.sticky_class,
+            gc_roots.thread_block,
+            gc_roots.monitor_used,
+            counts.primitive_array_dumps,
+            counts.object_array_dumps,
+            counts.class_dumps,
+            counts.instance_dumps,
         );
 
         format!("{capture_time}{top_summary}\n{heap_summary}")
     }
 }
 
+fn render_control_settings(settings: ControlSettingsInfo) -> String {
+    let on_off = |enabled| if enabled { "on" } else { "off" };
+    format!(
+        "\n..allocation traces: {}\n..CPU sampling: {}\n..stack trace depth: {}",
+        on_off(settings.allocation_traces),
+        on_off(settings.cpu_sampling),
+        settings.stack_trace_depth
+    )
+}
+
+fn render_heap_summary(summary: HeapSummaryInfo, memory_usage: &[ClassAllocationStats]) -> String {
+    let computed_bytes: u64 = memory_usage
+        .iter()
+        .map(|stats| stats.allocation_size_bytes)
+        .sum();
+    let computed_objects: u64 = memory_usage.iter().map(|stats| stats.instance_count).sum();
+    let discrepancy = if summary.live_bytes == 0 {
+        String::new()
+    } else {
+        let percent =
+            (computed_bytes as f64 - summary.live_bytes as f64) * 100.0 / summary.live_bytes as f64;
+        format!(", {percent:+.2}%")
+    };
+    format!(
+        "\n..live: {} in {} instances (computed shallow: {} in {computed_objects} objects{discrepancy})\n..allocated: {} in {} instances",
+        pretty_bytes_size(summary.live_bytes),
+        summary.live_instances,
+        pretty_bytes_size(computed_bytes),
+        pretty_bytes_size(summary.allocated_bytes),
+        summary.allocated_instances
+    )
+}
+
 const OBJECT_ALIGN: u32 = 8;
 
+// values listed with their count in the duplicated strings of the JSON
+const MOST_DUPLICATED_STRINGS: usize = 20;
+
 fn field_size(field_type: FieldType, id_size: u32) -> u32 {
     match field_type {
         FieldType::Object => id_size,
@@ -675,14 +1949,20 @@ fn field_size(field_type: FieldType, id_size: u32) -> u32 {
     }
 }
 
-fn primitive_byte_size(field_type: FieldType) -> u64 {
-    match field_type {
-        FieldType::Byte | FieldType::Bool => 1,
-        FieldType::Char | FieldType::Short => 2,
-        FieldType::Float | FieldType::Int => 4,
-        FieldType::Double | FieldType::Long => 8,
+// Named like the array classes of the dump, e.g. `boolean[]` for `[Z`.
+pub fn primitive_array_label(field_type: FieldType) -> String {
+    let descriptor = match field_type {
+        FieldType::Bool => "[Z",
+        FieldType::Byte => "[B",
+        FieldType::Char => "[C",
+        FieldType::Short => "[S",
+        FieldType::Int => "[I",
+        FieldType::Long => "[J",
+        FieldType::Float => "[F",
+        FieldType::Double => "[D",
         FieldType::Object => panic!("object type in primitive array"),
-    }
+    };
+    decode_class_name(descriptor)
 }
 
 fn primitive_array_size(id_size: u32, field_type: FieldType, number_of_elements: u32) -> u64 {
@@ -713,6 +1993,13 @@ fn array_header_size(id_size: u32) -> u64 {
     }
 }
 
+const fn is_monitor_root(kind: RootKind) -> bool {
+    matches!(
+        kind,
+        RootKind::MonitorUsed | RootKind::ThreadBlock | RootKind::JniMonitor
+    )
+}
+
 fn render_missing_class_warning(missing_class_ids: &AHashSet<u64>) -> Option<String> {
     if missing_class_ids.is_empty() {
         return None;
@@ -731,6 +2018,29 @@ fn render_missing_class_warning(missing_class_ids: &AHashSet<u64>) -> Option<Str
     ))
 }
 
+fn render_undecodable_strings_warning(undecodable_sequences: u64) -> Option<String> {
+    (undecodable_sequences > 0).then(|| {
+        format!(
+            "\nWarning: {undecodable_sequences} invalid modified UTF-8 sequence(s) found in the dump strings.\nThey are rendered as the replacement character '\u{fffd}'.\n"
+        )
+    })
+}
+
+// All end-of-run warnings, `None` if there is nothing to report.
+fn render_warnings(
+    missing_class_ids: &AHashSet<u64>,
+    undecodable_sequences: u64,
+) -> Option<String> {
+    let warnings: String = [
+        render_missing_class_warning(missing_class_ids),
+        render_undecodable_strings_warning(undecodable_sequences),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    (!warnings.is_empty()).then_some(warnings)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -748,17 +2058,40 @@ mod tests {
         assert_eq!(array_header_size(8), 16);
     }
 
+    #[test]
+    fn length_histograms_only_with_array_sizes() {
+        let mut heap = HeapCounters::new(DEFAULT_HEAP_TYPE, 0, false);
+        heap.add_primitive_array(FieldType::Int, 4, 32);
+        heap.add_object_array(0x10, 2, 24);
+        assert!(
+            heap.primitive_array_counters[&FieldType::Int]
+                .length_histogram
+                .is_none()
+        );
+        assert!(heap.object_array_counters[&0x10].length_histogram.is_none());
+
+        let mut heap = HeapCounters::new(DEFAULT_HEAP_TYPE, 0, true);
+        heap.add_primitive_array(FieldType::Int, 4, 32);
+        assert!(
+            heap.primitive_array_counters[&FieldType::Int]
+                .length_histogram
+                .is_some()
+        );
+    }
+
     #[test]
     fn instance_size_uses_mat_style_recursive_field_layout() {
-        let mut recorder = ResultRecorder::new(4, false, 0);
+        let mut recorder = ResultRecorder::new(4, ReportOptions::default(), 0);
         let mut records = vec![
             Record::Utf8String {
                 id: 10,
                 str: "java/lang/Object".into(),
+                undecodable_sequences: 0,
             },
             Record::Utf8String {
                 id: 11,
                 str: "com/example/Child".into(),
+                undecodable_sequences: 0,
             },
             Record::LoadClass(LoadClassData {
                 serial_number: 1,
@@ -808,11 +2141,13 @@ mod tests {
                 stack_trace_serial_number: 0,
                 class_object_id: 2,
                 data_size: 0,
+                field_values: None,
             }),
         ];
 
         recorder.record_records(&mut records);
-        let memory_usage = recorder.aggregate_memory_usage(&mut AHashSet::new());
+        let memory_usage =
+            recorder.aggregate_memory_usage(&recorder.counted_heaps(), &mut AHashSet::new());
         let child = memory_usage
             .iter()
             .find(|stats| stats.class_name == "com.example.Child")
@@ -824,28 +2159,31 @@ mod tests {
 
     #[test]
     fn primitive_array_size_uses_exact_padding_per_array() {
-        let mut recorder = ResultRecorder::new(4, false, 0);
+        let mut recorder = ResultRecorder::new(4, ReportOptions::default(), 0);
         let mut records = vec![
             Record::GcSegment(GcRecord::PrimitiveArrayDump {
                 object_id: 1,
                 stack_trace_serial_number: 0,
                 number_of_elements: 1,
                 element_type: FieldType::Bool,
+                zero_elements: 0,
             }),
             Record::GcSegment(GcRecord::PrimitiveArrayDump {
                 object_id: 2,
                 stack_trace_serial_number: 0,
                 number_of_elements: 2,
                 element_type: FieldType::Bool,
+                zero_elements: 0,
             }),
         ];
 
         recorder.record_records(&mut records);
-        let memory_usage = recorder.aggregate_memory_usage(&mut AHashSet::new());
+        let memory_usage =
+            recorder.aggregate_memory_usage(&recorder.counted_heaps(), &mut AHashSet::new());
         let bool_arrays = memory_usage
             .iter()
-            .find(|stats| stats.class_name == "bool[]")
-            .expect("bool[] stats should be present");
+            .find(|stats| stats.class_name == "boolean[]")
+            .expect("boolean[] stats should be present");
 
         assert_eq!(bool_arrays.largest_allocation_bytes, 16);
         assert_eq!(bool_arrays.allocation_size_bytes, 32);
@@ -853,11 +2191,12 @@ mod tests {
 
     #[test]
     fn object_array_size_uses_exact_padding_per_array() {
-        let mut recorder = ResultRecorder::new(4, false, 0);
+        let mut recorder = ResultRecorder::new(4, ReportOptions::default(), 0);
         let mut records = vec![
             Record::Utf8String {
                 id: 10,
                 str: "[Ljava/lang/Object;".into(),
+                undecodable_sequences: 0,
             },
             Record::LoadClass(LoadClassData {
                 serial_number: 1,
@@ -880,7 +2219,8 @@ mod tests {
         ];
 
         recorder.record_records(&mut records);
-        let memory_usage = recorder.aggregate_memory_usage(&mut AHashSet::new());
+        let memory_usage =
+            recorder.aggregate_memory_usage(&recorder.counted_heaps(), &mut AHashSet::new());
         let object_arrays = memory_usage
             .iter()
             .find(|stats| stats.class_name == "java.lang.Object[]")
@@ -890,21 +2230,124 @@ mod tests {
         assert_eq!(object_arrays.allocation_size_bytes, 40);
     }
 
+    #[test]
+    fn nested_array_class_names_are_decoded() {
+        let mut recorder = ResultRecorder::new(4, ReportOptions::default(), 0);
+        let mut records = vec![];
+        // JVM descriptors and the Android notation
+        for (id, name) in [(20, "[[C"), (21, "[[Ljava/lang/String;"), (22, "int[][]")] {
+            records.push(Record::Utf8String {
+                id,
+                str: name.into(),
+                undecodable_sequences: 0,
+            });
+            records.push(Record::LoadClass(LoadClassData {
+                serial_number: id as u32,
+                class_object_id: id,
+                stack_trace_serial_number: 0,
+                class_name_id: id,
+            }));
+            records.push(Record::GcSegment(GcRecord::ObjectArrayDump {
+                object_id: id + 100,
+                stack_trace_serial_number: 0,
+                number_of_elements: 1,
+                array_class_id: id,
+            }));
+        }
+
+        recorder.record_records(&mut records);
+        let mut class_names: Vec<_> = recorder
+            .aggregate_memory_usage(&recorder.counted_heaps(), &mut AHashSet::new())
+            .into_iter()
+            .map(|stats| stats.class_name)
+            .collect();
+        class_names.sort_unstable();
+
+        assert_eq!(
+            class_names,
+            vec!["char[][]", "int[][]", "java.lang.String[][]"]
+        );
+    }
+
+    #[test]
+    fn stack_frames_render_parameter_types() {
+        let mut recorder = ResultRecorder::new(4, ReportOptions::default(), 0);
+        let mut records = vec![
+            Record::Utf8String {
+                id: 1,
+                str: "wait".into(),
+                undecodable_sequences: 0,
+            },
+            Record::Utf8String {
+                id: 2,
+                str: "(J)V".into(),
+                undecodable_sequences: 0,
+            },
+            Record::Utf8String {
+                id: 3,
+                str: "Object.java".into(),
+                undecodable_sequences: 0,
+            },
+            Record::Utf8String {
+                id: 4,
+                str: "java/lang/Object".into(),
+                undecodable_sequences: 0,
+            },
+            Record::LoadClass(LoadClassData {
+                serial_number: 5,
+                class_object_id: 6,
+                stack_trace_serial_number: 0,
+                class_name_id: 4,
+            }),
+            Record::StackFrame(StackFrameData {
+                stack_frame_id: 7,
+                method_name_id: 1,
+                method_signature_id: 2,
+                source_file_name_id: 3,
+                class_serial_number: 5,
+                line_number: -3,
+            }),
+            Record::StackFrame(StackFrameData {
+                stack_frame_id: 8,
+                method_name_id: 1,
+                method_signature_id: 99,
+                source_file_name_id: 3,
+                class_serial_number: 5,
+                line_number: 502,
+            }),
+        ];
+
+        recorder.record_records(&mut records);
+        let mut missing_class_ids = AHashSet::new();
+
+        assert_eq!(
+            recorder.render_stack_frame(7, &mut missing_class_ids),
+            "java.lang.Object.wait(long) (Object.java:native method)"
+        );
+        // unknown signature
+        assert_eq!(
+            recorder.render_stack_frame(8, &mut missing_class_ids),
+            "java.lang.Object.wait (Object.java:502)"
+        );
+    }
+
     // Modern Android dumps reference class ids with no LoadClass/ClassDump
     // record; this used to panic the recorder thread.
     #[test]
     fn unknown_class_instance_falls_back_to_object_header_size() {
-        let mut recorder = ResultRecorder::new(4, false, 0);
+        let mut recorder = ResultRecorder::new(4, ReportOptions::default(), 0);
         let mut records = vec![Record::GcSegment(GcRecord::InstanceDump {
             object_id: 1,
             stack_trace_serial_number: 0,
             class_object_id: 0xABC,
             data_size: 0,
+            field_values: None,
         })];
 
         recorder.record_records(&mut records);
         let mut missing_class_ids = AHashSet::new();
-        let memory_usage = recorder.aggregate_memory_usage(&mut missing_class_ids);
+        let memory_usage =
+            recorder.aggregate_memory_usage(&recorder.counted_heaps(), &mut missing_class_ids);
         let unknown = memory_usage
             .iter()
             .find(|stats| stats.class_name == "<unknown class 0xabc>")
@@ -917,11 +2360,12 @@ mod tests {
 
     #[test]
     fn missing_super_class_falls_back_to_object_header_size() {
-        let mut recorder = ResultRecorder::new(4, false, 0);
+        let mut recorder = ResultRecorder::new(4, ReportOptions::default(), 0);
         let mut records = vec![
             Record::Utf8String {
                 id: 10,
                 str: "com/example/Orphan".into(),
+                undecodable_sequences: 0,
             },
             Record::LoadClass(LoadClassData {
                 serial_number: 1,
@@ -947,12 +2391,14 @@ mod tests {
                 stack_trace_serial_number: 0,
                 class_object_id: 1,
                 data_size: 0,
+                field_values: None,
             }),
         ];
 
         recorder.record_records(&mut records);
         let mut missing_class_ids = AHashSet::new();
-        let memory_usage = recorder.aggregate_memory_usage(&mut missing_class_ids);
+        let memory_usage =
+            recorder.aggregate_memory_usage(&recorder.counted_heaps(), &mut missing_class_ids);
         let orphan = memory_usage
             .iter()
             .find(|stats| stats.class_name == "com.example.Orphan")
@@ -965,7 +2411,7 @@ mod tests {
 
     #[test]
     fn thread_info_renders_placeholders_for_missing_frames_and_classes() {
-        let mut recorder = ResultRecorder::new(4, false, 0);
+        let mut recorder = ResultRecorder::new(4, ReportOptions::default(), 0);
         let mut records = vec![
             // frame 0x111 is never registered; frame 0x222 references the
             // never-loaded class serial number 7
@@ -994,11 +2440,413 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn thread_info_lists_frame_locals_when_requested() {
+        let records = || {
+            vec![
+                Record::StackTrace(StackTraceData {
+                    serial_number: 1,
+                    thread_serial_number: 5,
+                    number_of_frames: 2,
+                    stack_frame_ids: vec![0x111, 0x222],
+                }),
+                Record::GcSegment(GcRecord::PrimitiveArrayDump {
+                    object_id: 0xA,
+                    stack_trace_serial_number: 0,
+                    number_of_elements: 4,
+                    element_type: FieldType::Byte,
+                    zero_elements: 0,
+                }),
+                Record::GcSegment(GcRecord::RootJavaFrame {
+                    object_id: 0xA,
+                    thread_serial_number: 5,
+                    frame_number_in_stack_trace: 1,
+                }),
+                Record::GcSegment(GcRecord::RootJniLocal {
+                    object_id: 0xB,
+                    thread_serial_number: 5,
+                    frame_number_in_stack_trace: 0,
+                }),
+            ]
+        };
+
+        let mut recorder = ResultRecorder::new(4, ReportOptions::default(), 0);
+        recorder.record_records(&mut records());
+        let thread_info = recorder.render_thread_info(&mut AHashSet::new());
+        assert!(!thread_info.contains("local"));
+
+        let reports = ReportOptions {
+            locals: true,
+            ..ReportOptions::default()
+        };
+        let mut recorder = ResultRecorder::new(4, reports, 0);
+        recorder.record_records(&mut records());
+        let thread_info = recorder.render_thread_info(&mut AHashSet::new());
+        assert!(thread_info.contains(concat!(
+            "  at <unknown stack frame 0x111>\n",
+            "      - JNI local <0xb> (not in the dump)\n",
+            "  at <unknown stack frame 0x222>\n",
+            "      - local <0xa> (a byte[], 16.00bytes)\n",
+        )));
+    }
+
+    #[test]
+    fn monitor_report_flags_threads_waiting_on_the_same_monitor() {
+        let reports = ReportOptions {
+            locks: true,
+            ..ReportOptions::default()
+        };
+        let mut recorder = ResultRecorder::new(4, reports, 0);
+        let mut records = vec![
+            Record::Utf8String {
+                id: 10,
+                str: "java/lang/Object".into(),
+                undecodable_sequences: 0,
+            },
+            Record::Utf8String {
+                id: 11,
+                str: "wait".into(),
+                undecodable_sequences: 0,
+            },
+            Record::LoadClass(LoadClassData {
+                serial_number: 1,
+                class_object_id: 1,
+                stack_trace_serial_number: 0,
+                class_name_id: 10,
+            }),
+            Record::StackFrame(StackFrameData {
+                stack_frame_id: 0x100,
+                method_name_id: 11,
+                method_signature_id: 0,
+                source_file_name_id: 0,
+                class_serial_number: 1,
+                line_number: -3,
+            }),
+        ];
+        // threads 1 and 2 wait on 0xA from their second frame, thread 3 only
+        // references it
+        for thread_serial_number in 1..=3 {
+            let top_frame = if thread_serial_number == 3 {
+                0x200
+            } else {
+                0x100
+            };
+            records.push(Record::StackTrace(StackTraceData {
+                serial_number: thread_serial_number,
+                thread_serial_number,
+                number_of_frames: 2,
+                stack_frame_ids: vec![top_frame, 0x300],
+            }));
+            records.push(Record::GcSegment(GcRecord::RootJavaFrame {
+                object_id: 0xA,
+                thread_serial_number,
+                frame_number_in_stack_trace: 1,
+            }));
+        }
+        records.push(Record::GcSegment(GcRecord::RootMonitorUsed {
+            object_id: 0xA,
+        }));
+
+        recorder.record_records(&mut records);
+        let report = recorder.monitor_report(&mut AHashSet::new());
+
+        assert_eq!(report.monitors.len(), 1);
+        let monitor = &report.monitors[0];
+        let waiting: Vec<_> = monitor.waiting.iter().map(|r| r.thread.as_str()).collect();
+        assert_eq!(waiting, vec!["Thread 1", "Thread 2"]);
+        assert_eq!(monitor.referencing.len(), 1);
+        assert_eq!(monitor.referencing[0].thread, "Thread 3");
+        assert_eq!(report.contended().count(), 1);
+    }
+
+    #[test]
+    fn jstack_threads_lock_every_monitor_held_by_a_frame() {
+        let reports = ReportOptions {
+            jstack: true,
+            ..ReportOptions::default()
+        };
+        let mut recorder = ResultRecorder::new(4, reports, 0);
+        let mut records = vec![Record::StackTrace(StackTraceData {
+            serial_number: 1,
+            thread_serial_number: 1,
+            number_of_frames: 3,
+            stack_frame_ids: vec![0x100, 0x200, 0x300],
+        })];
+        // 0xA is held by two frames, 0xC is not a monitor
+        for (object_id, frame_number) in [(0xA, 2), (0xA, 1), (0xB, 0), (0xC, 0)] {
+            records.push(Record::GcSegment(GcRecord::RootJavaFrame {
+                object_id,
+                thread_serial_number: 1,
+                frame_number_in_stack_trace: frame_number,
+            }));
+        }
+        for object_id in [0xA, 0xB] {
+            records.push(Record::GcSegment(GcRecord::RootMonitorUsed { object_id }));
+        }
+
+        recorder.record_records(&mut records);
+        let threads = recorder.jstack_threads(&mut AHashSet::new());
+
+        assert!(threads[0].waiting_on.is_none());
+        let locked: Vec<_> = threads[0]
+            .locked
+            .iter()
+            .map(|(frame_number, monitor)| (*frame_number, monitor.object_id))
+            .collect();
+        assert_eq!(locked, vec![(0, 0xB), (1, 0xA)]);
+    }
+
+    #[test]
+    fn primitive_arrays_are_named_like_array_classes() {
+        assert_eq!(primitive_array_label(FieldType::Bool), "boolean[]");
+        assert_eq!(
+            primitive_array_label(FieldType::Double),
+            decode_class_name("[D")
+        );
+    }
+
+    #[test]
+    fn thread_stacks_use_start_thread_names() {
+        let mut recorder = ResultRecorder::new(4, ReportOptions::default(), 0);
+        let mut records = vec![
+            Record::Utf8String {
+                id: 10,
+                str: "worker-1".into(),
+                undecodable_sequences: 0,
+            },
+            Record::StartThread {
+                timestamp: 0,
+                thread_serial_number: 7,
+                thread_object_id: 0,
+                stack_trace_serial_number: 1,
+                thread_name_id: 10,
+                thread_group_name_id: 0,
+                thread_group_parent_name_id: 0,
+            },
+        ];
+        for (serial_number, thread_serial_number) in [(1, 7), (2, 8)] {
+            records.push(Record::StackTrace(StackTraceData {
+                serial_number,
+                thread_serial_number,
+                number_of_frames: 1,
+                stack_frame_ids: vec![0x111],
+            }));
+        }
+
+        recorder.record_records(&mut records);
+        let threads = recorder.thread_stacks(&mut AHashSet::new());
+
+        let names: Vec<_> = threads.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["worker-1", "Thread 2"]);
+        assert_eq!(threads[1].thread_serial_number, 8);
+        assert_eq!(threads[1].stack_trace_serial_number, 2);
+        assert_eq!(threads[0].frames, vec!["<unknown stack frame 0x111>"]);
+
+        let thread_info = recorder.render_thread_info(&mut AHashSet::new());
+        assert!(thread_info.contains("\nworker-1\n  at <unknown stack frame 0x111>\n"));
+        assert!(thread_info.contains("\nThread 2\n"));
+    }
+
+    #[test]
+    fn duplicated_strings_list_the_most_repeated_values() {
+        let mut recorder = ResultRecorder::new(4, ReportOptions::default(), 0);
+        let mut records: Vec<_> = ["a", "b", "b", "c", "c", "c"]
+            .into_iter()
+            .enumerate()
+            .map(|(id, str)| Record::Utf8String {
+                id: id as u64,
+                str: str.into(),
+                undecodable_sequences: 0,
+            })
+            .collect();
+
+        recorder.record_records(&mut records);
+        let duplicated = recorder
+            .duplicated_strings()
+            .expect("strings are duplicated");
+
+        assert_eq!(duplicated.duplicated_count, 3);
+        assert_eq!(duplicated.string_count, 6);
+        let values: Vec<_> = duplicated
+            .most_duplicated
+            .iter()
+            .map(|d| (d.value.as_str(), d.count))
+            .collect();
+        assert_eq!(values, vec![("c", 3), ("b", 2)]);
+        assert_eq!(
+            duplicated.render(),
+            "\nFound 3 duplicated strings out of 6 strings\n"
+        );
+    }
+
+    #[test]
+    fn thread_lifecycle_reports_ended_threads_still_in_heap() {
+        let reports = ReportOptions {
+            thread_lifecycle: true,
+            ..ReportOptions::default()
+        };
+        let mut recorder = ResultRecorder::new(4, reports, 1_608_192_273_831);
+        let mut records = vec![
+            Record::Utf8String {
+                id: 10,
+                str: "worker-1".into(),
+                undecodable_sequences: 0,
+            },
+            Record::Utf8String {
+                id: 11,
+                str: "main".into(),
+                undecodable_sequences: 0,
+            },
+        ];
+        // the name of the third thread is not in the dump
+        for (thread_serial_number, thread_object_id, thread_name_id) in
+            [(1, 0x20, 10), (2, 0x21, 10), (3, 0x22, 99)]
+        {
+            records.push(Record::StartThread {
+                timestamp: 0,
+                thread_serial_number,
+                thread_object_id,
+                stack_trace_serial_number: 0,
+                thread_name_id,
+                thread_group_name_id: 11,
+                thread_group_parent_name_id: 0,
+            });
+        }
+        records.push(Record::StackTrace(StackTraceData {
+            serial_number: 1,
+            thread_serial_number: 3,
+            number_of_frames: 1,
+            stack_frame_ids: vec![0x111],
+        }));
+        records.push(Record::EndThread {
+            timestamp: 2_000_000,
+            thread_serial_number: 2,
+        });
+        records.push(Record::GcSegment(GcRecord::InstanceDump {
+            object_id: 0x21,
+            stack_trace_serial_number: 0,
+            class_object_id: 1,
+            data_size: 0,
+            field_values: None,
+        }));
+
+        recorder.record_records(&mut records);
+        let report = recorder.thread_lifecycle_report();
+
+        assert_eq!(report.threads.len(), 3);
+        // the objects are not indexed for the report
+        assert!(recorder.object_shapes.is_empty());
+        assert!(report.threads[0].alive);
+        assert!(!report.threads[0].object_in_heap);
+        let ended = &report.threads[1];
+        assert_eq!(ended.group.as_deref(), Some("main"));
+        assert_eq!(ended.started_at, Some(1_608_192_273_831));
+        assert_eq!(ended.ended_at, Some(1_608_192_275_831));
+        assert!(!ended.alive);
+        assert!(ended.object_in_heap);
+        // named like in the stack traces
+        assert_eq!(report.threads[2].name, "Thread 1");
+    }
+
+    #[test]
+    fn alloc_traces_break_down_histogram_by_stack_trace() {
+        let reports = ReportOptions {
+            alloc_traces: true,
+            ..ReportOptions::default()
+        };
+        let mut recorder = ResultRecorder::new(4, reports, 0);
+        let mut records = vec![Record::StackTrace(StackTraceData {
+            serial_number: 5,
+            thread_serial_number: 1,
+            number_of_frames: 1,
+            stack_frame_ids: vec![0x111],
+        })];
+        for (object_id, stack_trace_serial_number) in [(1, 5), (2, 5), (3, 6)] {
+            records.push(Record::GcSegment(GcRecord::PrimitiveArrayDump {
+                object_id,
+                stack_trace_serial_number,
+                number_of_elements: 4,
+                element_type: FieldType::Byte,
+                zero_elements: 0,
+            }));
+        }
+
+        recorder.record_records(&mut records);
+        let report = recorder.alloc_trace_report(&mut AHashSet::new());
+
+        assert_eq!(report.traces.len(), 2);
+        let traced = &report.traces[0];
+        assert_eq!(traced.serial_number, 5);
+        assert_eq!(traced.frames, vec!["<unknown stack frame 0x111>"]);
+        assert_eq!(traced.memory_usage[0].class_name, "byte[]");
+        assert_eq!(traced.memory_usage[0].instance_count, 2);
+        assert!(report.traces[1].frames.is_empty());
+    }
+
+    #[test]
+    fn class_loaders_are_named_after_their_class() {
+        let reports = ReportOptions {
+            class_loaders: true,
+            ..ReportOptions::default()
+        };
+        let mut recorder = ResultRecorder::new(4, reports, 0);
+        let mut records = vec![];
+        for (id, name) in [(10, "com/example/Plugin"), (11, "com/example/PluginLoader")] {
+            records.push(Record::Utf8String {
+                id,
+                str: name.into(),
+                undecodable_sequences: 0,
+            });
+        }
+        for (class_object_id, class_name_id) in [(1, 10), (2, 11)] {
+            records.push(Record::LoadClass(LoadClassData {
+                serial_number: class_object_id as u32,
+                class_object_id,
+                stack_trace_serial_number: 0,
+                class_name_id,
+            }));
+        }
+        for (class_object_id, class_loader_object_id) in [(1, 0x50), (2, 0)] {
+            records.push(Record::GcSegment(GcRecord::ClassDump(Box::new(
+                ClassDumpFields::new(class_object_id, 0, 0, 0, vec![], vec![], vec![])
+                    .with_class_loader(class_loader_object_id),
+            ))));
+        }
+        for (object_id, class_object_id) in [(0x50, 2), (0x51, 1)] {
+            records.push(Record::GcSegment(GcRecord::InstanceDump {
+                object_id,
+                stack_trace_serial_number: 0,
+                class_object_id,
+                data_size: 0,
+                field_values: None,
+            }));
+        }
+
+        recorder.record_records(&mut records);
+        let memory_usage =
+            recorder.aggregate_memory_usage(&recorder.counted_heaps(), &mut AHashSet::new());
+
+        let class_loader = |class_name: &str| {
+            memory_usage
+                .iter()
+                .find(|stats| stats.class_name == class_name)
+                .and_then(|stats| stats.class_loader.clone())
+        };
+        assert_eq!(
+            class_loader("com.example.Plugin").as_deref(),
+            Some("com.example.PluginLoader@0x50")
+        );
+        assert_eq!(
+            class_loader("com.example.PluginLoader").as_deref(),
+            Some("bootstrap")
+        );
+    }
+
     // End-to-end through the recorder thread: a record stream referencing an
     // unknown class must produce a result carrying the warning, not a panic.
     #[test]
     fn recorder_thread_reports_missing_classes_as_warning() {
-        let recorder = ResultRecorder::new(4, false, 0);
+        let recorder = ResultRecorder::new(4, ReportOptions::default(), 0);
         let (send_records, receive_records) = crossbeam_channel::unbounded();
         let (send_result, receive_result) = crossbeam_channel::unbounded();
         let (send_pooled_vec, _receive_pooled_vec) = crossbeam_channel::unbounded();
@@ -1012,6 +2860,7 @@ mod tests {
                 stack_trace_serial_number: 0,
                 class_object_id: 0xABC,
                 data_size: 0,
+                field_values: None,
             })])
             .expect("recorder should accept records");
         drop(send_records);
@@ -1026,17 +2875,421 @@ mod tests {
         assert!(warnings.contains("0xabc"));
     }
 
+    #[test]
+    fn undecodable_strings_are_counted_in_the_warnings() {
+        let mut recorder = ResultRecorder::new(4, ReportOptions::default(), 0);
+        let mut records = vec![
+            Record::Utf8String {
+                id: 1,
+                str: "\u{fffd}".into(),
+                undecodable_sequences: 1,
+            },
+            Record::Utf8String {
+                id: 2,
+                str: "\u{fffd}\u{fffd}".into(),
+                undecodable_sequences: 2,
+            },
+        ];
+
+        recorder.record_records(&mut records);
+        let mut missing_class_ids = AHashSet::new();
+        missing_class_ids.insert(0xABC);
+        let warnings = render_warnings(&missing_class_ids, recorder.undecodable_utf8_sequences)
+            .expect("warnings should be present");
+
+        assert!(warnings.contains("1 class definition(s)"));
+        assert!(warnings.contains("3 invalid modified UTF-8 sequence(s)"));
+        assert_eq!(render_warnings(&AHashSet::new(), 0), None);
+    }
+
+    #[test]
+    fn objects_are_counted_per_heap_and_shared_heaps_can_be_excluded() {
+        let reports = ReportOptions {
+            heaps: true,
+            exclude_shared_heaps: true,
+            ..ReportOptions::default()
+        };
+        let mut recorder = ResultRecorder::new(4, reports, 0);
+        let int_array = |object_id| {
+            Record::GcSegment(GcRecord::PrimitiveArrayDump {
+                object_id,
+                stack_trace_serial_number: 0,
+                number_of_elements: 2,
+                element_type: FieldType::Int,
+                zero_elements: 0,
+            })
+        };
+        let heap_info = |heap_type: u8, heap_name_id| {
+            Record::GcSegment(GcRecord::HeapDumpInfo {
+                heap_type: u32::from(heap_type),
+                heap_name_id,
+            })
+        };
+        let mut records = vec![
+            Record::Utf8String {
+                id: 100,
+                str: "zygote".into(),
+                undecodable_sequences: 0,
+            },
+            heap_info(b'Z', 100),
+            int_array(1),
+            int_array(2),
+            // unnamed heap
+            heap_info(b'A', 0),
+            int_array(3),
+            // back to an already seen heap
+            heap_info(b'Z', 100),
+            int_array(4),
+        ];
+
+        recorder.record_records(&mut records);
+        let mut missing_class_ids = AHashSet::new();
+        let memory_usage =
+            recorder.aggregate_memory_usage(&recorder.counted_heaps(), &mut missing_class_ids);
+        let heaps = recorder.heap_usages(&mut missing_class_ids);
+
+        assert_eq!(memory_usage.len(), 1);
+        assert_eq!(memory_usage[0].instance_count, 1);
+        // the default heap is empty
+        assert_eq!(heaps.len(), 2);
+        assert_eq!(heaps[0].name, "zygote");
+        assert!(heaps[0].shared && heaps[0].excluded);
+        assert_eq!(heaps[0].memory_usage[0].instance_count, 3);
+        assert_eq!(heaps[1].name, "app");
+        assert!(!heaps[1].excluded);
+        // the heap summary is compared to every heap
+        let all_heaps =
+            recorder.aggregate_memory_usage(&recorder.all_heaps(), &mut missing_class_ids);
+        assert_eq!(all_heaps[0].instance_count, 4);
+    }
+
+    #[test]
+    fn objects_are_counted_per_heap_dump() {
+        let reports = ReportOptions {
+            dumps: true,
+            ..ReportOptions::default()
+        };
+        let mut recorder = ResultRecorder::new(4, reports, 1_000);
+        let start = |timestamp, segment| Record::HeapDumpStart {
+            length: 0,
+            timestamp,
+            segment,
+        };
+        let byte_array = |object_id| {
+            Record::GcSegment(GcRecord::PrimitiveArrayDump {
+                object_id,
+                stack_trace_serial_number: 0,
+                number_of_elements: 4,
+                element_type: FieldType::Byte,
+                zero_elements: 0,
+            })
+        };
+        let mut records = vec![
+            // first dump in two segments
+            start(2_000, true),
+            byte_array(1),
+            start(3_000, true),
+            byte_array(2),
+            Record::HeapDumpEnd { length: 0 },
+            // second dump as a whole heap dump record
+            start(5_000, false),
+            byte_array(3),
+        ];
+
+        recorder.record_records(&mut records);
+        let report = recorder.dump_report(&mut AHashSet::new());
+
+        assert_eq!(report.dumps.len(), 2);
+        assert_eq!(report.dumps[0].timestamp, Some(1_002));
+        assert_eq!(report.dumps[0].memory_usage[0].instance_count, 2);
+        assert_eq!(report.dumps[1].timestamp, Some(1_005));
+        assert_eq!(report.dumps[1].memory_usage[0].instance_count, 1);
+    }
+
+    #[test]
+    fn android_report_decodes_bitmaps_and_destroyed_activities() {
+        let reports = ReportOptions {
+            android: true,
+            ..ReportOptions::default()
+        };
+        let mut recorder = ResultRecorder::new(4, reports, 0);
+        let field = |name_id, field_type| FieldInfo {
+            name_id,
+            field_type,
+        };
+        let mut records = vec![];
+        for (id, name) in [
+            (100, "java/lang/Object"),
+            (101, "android/app/Activity"),
+            (102, "com/example/MainActivity"),
+            (103, "android/graphics/Bitmap"),
+            (110, "mDestroyed"),
+            (111, "mFinished"),
+            (112, "count"),
+            (113, "mWidth"),
+            (114, "mHeight"),
+            (115, "mBuffer"),
+        ] {
+            records.push(Record::Utf8String {
+                id,
+                str: name.into(),
+                undecodable_sequences: 0,
+            });
+        }
+        for (class_object_id, class_name_id) in [(1, 100), (2, 101), (3, 102), (4, 103)] {
+            records.push(Record::LoadClass(LoadClassData {
+                serial_number: class_object_id as u32,
+                class_object_id,
+                stack_trace_serial_number: 0,
+                class_name_id,
+            }));
+        }
+        // instances dumped before their classes
+        let mut activity_fields = 7i32.to_be_bytes().to_vec();
+        activity_fields.extend_from_slice(&[1, 0]);
+        let mut bitmap_fields = vec![];
+        for value in [2u32, 3, 50] {
+            bitmap_fields.extend_from_slice(&value.to_be_bytes());
+        }
+        for (object_id, class_object_id, field_values) in
+            [(10, 3, activity_fields), (11, 4, bitmap_fields)]
+        {
+            records.push(Record::GcSegment(GcRecord::InstanceDump {
+                object_id,
+                stack_trace_serial_number: 0,
+                class_object_id,
+                data_size: field_values.len() as u32,
+                field_values: Some(Box::new(field_values)),
+            }));
+        }
+        records.push(Record::GcSegment(GcRecord::PrimitiveArrayDump {
+            object_id: 50,
+            stack_trace_serial_number: 0,
+            number_of_elements: 24,
+            element_type: FieldType::Byte,
+            zero_elements: 0,
+        }));
+        for (class_object_id, super_class_object_id, instance_fields) in [
+            (1, 0, vec![]),
+            (
+                2,
+                1,
+                vec![field(110, FieldType::Bool), field(111, FieldType::Bool)],
+            ),
+            (3, 2, vec![field(112, FieldType::Int)]),
+            (
+                4,
+                1,
+                vec![
+                    field(113, FieldType::Int),
+                    field(114, FieldType::Int),
+                    field(115, FieldType::Object),
+                ],
+            ),
+        ] {
+            records.push(Record::GcSegment(GcRecord::ClassDump(Box::new(
+                ClassDumpFields::new(
+                    class_object_id,
+                    0,
+                    super_class_object_id,
+                    0,
+                    vec![],
+                    vec![],
+                    instance_fields,
+                ),
+            ))));
+        }
+
+        recorder.record_records(&mut records);
+        let report = recorder.android_report(&mut AHashSet::new());
+
+        assert_eq!(report.bitmaps.len(), 1);
+        assert_eq!((report.bitmaps[0].width, report.bitmaps[0].height), (2, 3));
+        // 12 bytes header + 24 elements
+        assert_eq!(report.bitmaps[0].buffer_bytes, Some(40));
+        assert_eq!(report.activities.len(), 1);
+        assert_eq!(report.activities[0].class_name, "com.example.MainActivity");
+        assert_eq!(report.activities[0].leak_reasons, vec!["destroyed"]);
+        assert!(report.fragments.is_empty());
+    }
+
+    #[test]
+    fn android_keeps_only_the_instances_of_candidate_classes() {
+        let reports = ReportOptions {
+            android: true,
+            ..ReportOptions::default()
+        };
+        let instance = |object_id, class_object_id| {
+            Record::GcSegment(GcRecord::InstanceDump {
+                object_id,
+                stack_trace_serial_number: 0,
+                class_object_id,
+                data_size: 4,
+                field_values: Some(Box::new(vec![0; 4])),
+            })
+        };
+        let mut records = vec![];
+        for (id, name) in [(100, "java/lang/Object"), (101, "com/example/Plain")] {
+            records.push(Record::Utf8String {
+                id,
+                str: name.into(),
+                undecodable_sequences: 0,
+            });
+            records.push(Record::LoadClass(LoadClassData {
+                serial_number: id as u32,
+                class_object_id: id - 99,
+                stack_trace_serial_number: 0,
+                class_name_id: id,
+            }));
+        }
+        // no framework class is loaded, no class dump can match
+        records.push(instance(10, 2));
+
+        let mut recorder = ResultRecorder::new(4, reports, 0);
+        recorder.record_records(&mut records);
+        assert!(recorder.android_instances.is_empty());
+
+        // once the hierarchy is dumped, a non Android class is resolved
+        records.clear();
+        records.push(Record::Utf8String {
+            id: 102,
+            str: "android/app/Activity".into(),
+            undecodable_sequences: 0,
+        });
+        records.push(Record::LoadClass(LoadClassData {
+            serial_number: 3,
+            class_object_id: 3,
+            stack_trace_serial_number: 0,
+            class_name_id: 102,
+        }));
+        for (class_object_id, super_class_object_id) in [(1, 0), (2, 1)] {
+            records.push(Record::GcSegment(GcRecord::ClassDump(Box::new(
+                ClassDumpFields::new(
+                    class_object_id,
+                    0,
+                    super_class_object_id,
+                    0,
+                    vec![],
+                    vec![],
+                    vec![],
+                ),
+            ))));
+        }
+        records.push(instance(11, 2));
+        // an activity subclass may still be dumped
+        records.push(instance(12, 4));
+
+        recorder.record_records(&mut records);
+        let kept: Vec<_> = recorder.android_instances.iter().map(|i| i.0).collect();
+        assert_eq!(kept, vec![12]);
+        assert_eq!(recorder.android_class_by_id.get(&2), Some(&None));
+    }
+
+    #[test]
+    fn gc_roots_resolve_objects_dumped_before_the_roots() {
+        let reports = ReportOptions {
+            gc_roots: true,
+            ..ReportOptions::default()
+        };
+        let mut recorder = ResultRecorder::new(4, reports, 0);
+        // HotSpot order: objects first, roots last
+        let mut records = vec![
+            Record::GcSegment(GcRecord::PrimitiveArrayDump {
+                object_id: 1,
+                stack_trace_serial_number: 0,
+                number_of_elements: 4,
+                element_type: FieldType::Int,
+                zero_elements: 0,
+            }),
+            Record::GcSegment(GcRecord::RootJniGlobal {
+                object_id: 1,
+                jni_global_ref_id: 0,
+            }),
+            Record::GcSegment(GcRecord::RootJniGlobal {
+                object_id: 2,
+                jni_global_ref_id: 0,
+            }),
+        ];
+
+        recorder.record_records(&mut records);
+        let report = recorder.gc_root_report(&mut AHashSet::new());
+
+        let jni_global = &report.root_types[0];
+        assert_eq!(jni_global.roots, 2);
+        assert_eq!(jni_global.unresolved_objects, 1);
+        assert_eq!(jni_global.classes[0].class_name, "int[]");
+        // 12 bytes header + 16 bytes elements, 8-byte aligned
+        assert_eq!(jni_global.shallow_size_bytes, 32);
+    }
+
+    #[test]
+    fn record_counts_include_android_roots() {
+        let mut recorder = ResultRecorder::new(4, ReportOptions::default(), 0);
+        recorder.record_records(&mut [
+            Record::GcSegment(GcRecord::RootInternedString { object_id: 1 }),
+            Record::GcSegment(GcRecord::RootInternedString { object_id: 2 }),
+            Record::GcSegment(GcRecord::RootVmInternal { object_id: 3 }),
+            Record::GcSegment(GcRecord::RootJniMonitor {
+                object_id: 4,
+                thread_serial_number: 1,
+                frame_number_in_stack_trace: 0,
+            }),
+        ]);
+
+        let gc_roots = recorder.record_counts().gc_roots;
+        assert_eq!(gc_roots.interned_string, 2);
+        assert_eq!(gc_roots.vm_internal, 1);
+        assert_eq!(gc_roots.jni_monitor, 1);
+        assert_eq!(gc_roots.finalizing, 0);
+    }
+
     #[test]
     fn summary_renders_capture_time_only_when_present() {
-        let with_timestamp = ResultRecorder::new(4, false, 1_608_192_273_831);
+        let with_timestamp = ResultRecorder::new(4, ReportOptions::default(), 1_608_192_273_831);
         assert!(
             with_timestamp
-                .render_summary()
+                .render_summary(&[])
                 .starts_with("\nDump captured at 2020-12-17 08:04:33 UTC.\n")
         );
 
-        let without_timestamp = ResultRecorder::new(4, false, 0);
-        assert!(!without_timestamp.render_summary().contains("captured"));
+        let without_timestamp = ResultRecorder::new(4, ReportOptions::default(), 0);
+        assert!(!without_timestamp.render_summary(&[]).contains("captured"));
+    }
+
+    #[test]
+    fn summary_renders_heap_summary_against_computed_totals() {
+        let mut recorder = ResultRecorder::new(4, ReportOptions::default(), 0);
+        assert!(!recorder.render_summary(&[]).contains("..live"));
+
+        recorder.record_records(&mut [
+            Record::HeapSummary {
+                total_live_bytes: 200,
+                total_live_instances: 4,
+                total_bytes_allocated: 1024,
+                total_instances_allocated: 10,
+            },
+            Record::ControlSettings {
+                flags: 0x2,
+                stack_trace_depth: 16,
+            },
+        ]);
+        assert_eq!(
+            recorder.control_settings_info,
+            Some(ControlSettingsInfo {
+                allocation_traces: false,
+                cpu_sampling: true,
+                stack_trace_depth: 16,
+            })
+        );
+
+        let memory_usage = [ClassAllocationStats::new("byte[]".to_string(), 3, 100, 190)];
+        let summary = recorder.render_summary(&memory_usage);
+        assert!(summary.contains(
+            "Control settings: 1\n..allocation traces: off\n..CPU sampling: on\n..stack trace depth: 16\n"
+        ));
+        assert!(summary.contains(
+            "Heap summaries: 1\n..live: 200.00bytes in 4 instances (computed shallow: 190.00bytes in 3 objects, -5.00%)\n..allocated: 1.00KiB in 10 instances\n"
+        ));
     }
 
     #[test]
@@ -5,6 +5,7 @@ use indicatif::{ProgressBar, ProgressStyle};
 
 use crossbeam_channel::{Receiver, Sender};
 
+use crate::args::ReportOptions;
 use crate::errors::HprofSlurpError;
 use crate::errors::HprofSlurpError::{
     InvalidHeaderSize, InvalidHprofFile, InvalidIdSize, StdThreadError,
@@ -26,7 +27,7 @@ pub const READ_BUFFER_SIZE: usize = 64 * 1024 * 1024;
 pub fn slurp_file(
     file_path: &str,
     debug_mode: bool,
-    list_strings: bool,
+    reports: ReportOptions,
 ) -> Result<(FileHeader, RenderedResult), HprofSlurpError> {
     let file = File::open(file_path)?;
     let file_len = file.metadata()?.len() as usize;
@@ -86,6 +87,8 @@ pub fn slurp_file(
     let stream_parser = HprofRecordStreamParser::new(
         debug_mode,
         id_size,
+        reports.zero_arrays,
+        reports.android,
         file_len,
         FILE_HEADER_LENGTH,
         initial_loop_buffer,
@@ -101,7 +104,7 @@ pub fn slurp_file(
     )?;
 
     // Init result recorder
-    let result_recorder = ResultRecorder::new(id_size, list_strings, header.timestamp);
+    let result_recorder = ResultRecorder::new(id_size, reports, header.timestamp);
     let recorder_thread = result_recorder.start(receive_records, send_result, send_pooled_vec)?;
 
     // Init progress bar
@@ -156,6 +159,7 @@ pub fn slurp_header(reader: &mut BufReader<File>) -> Result<FileHeader, HprofSlu
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::rendered_result::{DumpInfo, JsonResult, json_schema};
     use std::fs;
 
     const FILE_PATH_32: &str = "test-heap-dumps/hprof-32.bin";
@@ -193,24 +197,89 @@ mod tests {
 
     #[test]
     fn supported_32_bits() {
-        let result = slurp_file(FILE_PATH_32, false, false);
+        let result = slurp_file(FILE_PATH_32, false, ReportOptions::default());
         assert!(result.is_ok());
         validate_gold_rendered_result(result.unwrap().1, FILE_PATH_RESULT_32);
     }
 
     #[test]
     fn supported_64_bits() {
-        let result = slurp_file(FILE_PATH_64, false, false);
+        let result = slurp_file(FILE_PATH_64, false, ReportOptions::default());
         assert!(result.is_ok());
         validate_gold_rendered_result(result.unwrap().1, FILE_PATH_RESULT_64);
     }
 
+    // The `--json` output of a gold dump, with every optional report enabled.
+    fn validate_json_schema(file_path: &str) {
+        let reports = ReportOptions {
+            list_strings: true,
+            array_sizes: true,
+            zero_arrays: true,
+            mostly_zero_percent: Some(90),
+            heaps: true,
+            dumps: true,
+            diff_dumps: true,
+            android: true,
+            alloc_traces: true,
+            gc_roots: true,
+            locks: true,
+            group_threads: true,
+            thread_lifecycle: true,
+            ..ReportOptions::default()
+        };
+        let (file_header, mut rendered_result) = slurp_file(file_path, false, reports).unwrap();
+        let dump_info = DumpInfo::new(
+            file_path.to_string(),
+            fs::metadata(file_path).unwrap().len(),
+            file_header.format,
+            file_header.size_pointers,
+            file_header.timestamp,
+        );
+        let json_result = JsonResult::from_rendered(dump_info, &mut rendered_result, 20);
+        let json = serde_json::to_value(&json_result).unwrap();
+        for section in [
+            "strings",
+            "thread_groups",
+            "zero_arrays",
+            "heaps",
+            "dumps",
+            "android",
+            "alloc_traces",
+            "gc_roots",
+            "locks",
+            "thread_lifecycle",
+        ] {
+            assert!(
+                json.get(section).is_some(),
+                "{file_path}: missing {section}"
+            );
+        }
+        assert!(json["heap"].get("array_size_distribution").is_some());
+
+        let validator = jsonschema::validator_for(&json_schema()).expect("schema should be valid");
+        let errors: Vec<_> = validator
+            .iter_errors(&json)
+            .map(|error| format!("{} at {}", error, error.instance_path()))
+            .collect();
+        assert!(errors.is_empty(), "{file_path}: {errors:#?}");
+    }
+
+    #[test]
+    fn json_output_matches_schema_32_bits() {
+        validate_json_schema(FILE_PATH_32);
+    }
+
+    #[test]
+    fn json_output_matches_schema_64_bits() {
+        validate_json_schema(FILE_PATH_64);
+    }
+
     #[test]
     fn supported_android_1_0_3_extension_records() {
         // Regression: this dump emits Android extension GC records that older
         // versions panicked on ("unhandled gc record tag 138" = ROOT_FINALIZING).
         // It must now parse cleanly end-to-end and match the gold output.
-        let result = slurp_file(FILE_PATH_ANDROID, false, false);
+        let result = slurp_file(FILE_PATH_ANDROID, false, ReportOptions::default());
         assert!(result.is_ok());
         validate_gold_rendered_result(result.unwrap().1, FILE_PATH_RESULT_ANDROID);
     }
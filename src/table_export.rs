@@ -0,0 +1,136 @@
+//! `--csv` and `--tsv` — every class of the histogram, without the top cap
+//! of the other outputs, for spreadsheets and notebooks. `--package-column`
+//! and `--classloader-column` add the package and the class loader of each
+//! class.
+
+use std::fmt::Write;
+
+use crate::descriptor::package_name;
+use crate::rendered_result::ClassAllocationStats;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableFormat {
+    Csv,
+    Tsv,
+}
+
+impl TableFormat {
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Csv => "CSV",
+            Self::Tsv => "TSV",
+        }
+    }
+
+    const fn separator(self) -> char {
+        match self {
+            Self::Csv => ',',
+            Self::Tsv => '\t',
+        }
+    }
+
+    // RFC 4180 quoting for CSV, TSV cannot quote so separators are replaced.
+    pub fn field(self, value: &str) -> String {
+        match self {
+            Self::Csv if value.contains([',', '"', '\n', '\r']) => {
+                format!("\"{}\"", value.replace('"', "\"\""))
+            }
+            Self::Csv => value.to_string(),
+            Self::Tsv => value.replace(['\t', '\n', '\r'], " "),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TableColumns {
+    pub package: bool,
+    pub class_loader: bool,
+}
+
+// One row per class, the largest total size first.
+pub fn render(
+    memory_usage: &[ClassAllocationStats],
+    format: TableFormat,
+    columns: TableColumns,
+) -> String {
+    let separator = format.separator();
+    let mut header = vec![
+        "class_name",
+        "instance_count",
+        "largest_allocation_bytes",
+        "allocation_size_bytes",
+    ];
+    if columns.package {
+        header.push("package");
+    }
+    if columns.class_loader {
+        header.push("class_loader");
+    }
+    let mut out = header.join(&separator.to_string());
+    out.push('\n');
+
+    let mut rows: Vec<_> = memory_usage.iter().collect();
+    rows.sort_by_key(|stats| std::cmp::Reverse(stats.allocation_size_bytes));
+    for stats in rows {
+        let _ = write!(
+            out,
+            "{}{separator}{}{separator}{}{separator}{}",
+            format.field(&stats.class_name),
+            stats.instance_count,
+            stats.largest_allocation_bytes,
+            stats.allocation_size_bytes
+        );
+        if columns.package {
+            let _ = write!(
+                out,
+                "{separator}{}",
+                format.field(package_name(&stats.class_name))
+            );
+        }
+        if columns.class_loader {
+            let class_loader = stats.class_loader.as_deref().unwrap_or_default();
+            let _ = write!(out, "{separator}{}", format.field(class_loader));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memory_usage() -> Vec<ClassAllocationStats> {
+        vec![
+            ClassAllocationStats::new("java.util.HashMap$Node[]".to_string(), 2, 48, 80),
+            ClassAllocationStats::new("byte[]".to_string(), 1, 96, 96)
+                .with_class_loader(Some("bootstrap".to_string())),
+        ]
+    }
+
+    #[test]
+    fn every_row_is_written_with_the_optional_columns() {
+        let csv = render(&memory_usage(), TableFormat::Csv, TableColumns::default());
+        assert_eq!(
+            csv,
+            "class_name,instance_count,largest_allocation_bytes,allocation_size_bytes\nbyte[],1,96,96\njava.util.HashMap$Node[],2,48,80\n"
+        );
+
+        let columns = TableColumns {
+            package: true,
+            class_loader: true,
+        };
+        let tsv = render(&memory_usage(), TableFormat::Tsv, columns);
+        assert!(tsv.starts_with(
+            "class_name\tinstance_count\tlargest_allocation_bytes\tallocation_size_bytes\tpackage\tclass_loader\n"
+        ));
+        assert!(tsv.contains("\nbyte[]\t1\t96\t96\t\tbootstrap\n"));
+        assert!(tsv.ends_with("\njava.util.HashMap$Node[]\t2\t48\t80\tjava.util\t\n"));
+    }
+
+    #[test]
+    fn csv_fields_are_quoted() {
+        assert_eq!(TableFormat::Csv.field("a,\"b\""), "\"a,\"\"b\"\"\"");
+        assert_eq!(TableFormat::Tsv.field("a\tb"), "a b");
+    }
+}
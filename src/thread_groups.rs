@@ -0,0 +1,97 @@
+//! `--group-threads` — threads sharing the exact same stack trace are listed
+//! once, so the odd thread is not buried under hundreds of idle workers.
+
+use std::fmt::Write;
+
+use ahash::AHashMap;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::utils::pluralize;
+
+#[derive(Serialize, JsonSchema, Clone, Debug)]
+pub struct ThreadGroup {
+    pub threads: Vec<String>,
+    pub frames: Vec<String>,
+}
+
+impl ThreadGroup {
+    // Groups `(thread name, rendered frames)` pairs by identical frames, the
+    // largest groups first and otherwise in the order of their first thread.
+    pub fn group(threads: Vec<(String, Vec<String>)>) -> Vec<Self> {
+        let mut groups: Vec<Self> = Vec::new();
+        let mut group_index_by_frames: AHashMap<Vec<String>, usize> = AHashMap::new();
+        for (thread, frames) in threads {
+            if let Some(index) = group_index_by_frames.get(&frames) {
+                groups[*index].threads.push(thread);
+            } else {
+                group_index_by_frames.insert(frames.clone(), groups.len());
+                groups.push(Self {
+                    threads: vec![thread],
+                    frames,
+                });
+            }
+        }
+        // stable sort keeps the order of first appearance within a size
+        groups.sort_by_key(|group| std::cmp::Reverse(group.threads.len()));
+        groups
+    }
+
+    pub fn render(groups: &[Self]) -> String {
+        let mut out = String::new();
+        let thread_count: usize = groups.iter().map(|g| g.threads.len()).sum();
+        let _ = writeln!(
+            out,
+            "\nFound {} with stacktraces in {}:",
+            pluralize(thread_count, "thread", "threads"),
+            pluralize(groups.len(), "group", "groups")
+        );
+        for group in groups {
+            let _ = writeln!(
+                out,
+                "\n{} with this stack: {}",
+                pluralize(group.threads.len(), "thread", "threads"),
+                group.threads.join(", ")
+            );
+            for frame in &group.frames {
+                let _ = writeln!(out, "  at {frame}");
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn thread(name: &str, frames: &[&str]) -> (String, Vec<String>) {
+        (
+            name.to_string(),
+            frames.iter().map(ToString::to_string).collect(),
+        )
+    }
+
+    #[test]
+    fn identical_stacks_are_grouped_largest_first() {
+        let idle = ["sun.misc.Unsafe.park", "java.lang.Thread.run"];
+        let groups = ThreadGroup::group(vec![
+            thread("main", &["com.example.Main.main"]),
+            thread("worker-1", &idle),
+            thread("busy", &["com.example.Handler.handle"]),
+            thread("worker-2", &idle),
+        ]);
+
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[0].threads, vec!["worker-1", "worker-2"]);
+        assert_eq!(groups[1].threads, vec!["main"]);
+        assert_eq!(groups[2].threads, vec!["busy"]);
+
+        let rendered = ThreadGroup::render(&groups);
+        assert!(rendered.starts_with("\nFound 4 threads with stacktraces in 3 groups:\n"));
+        assert!(rendered.contains(
+            "\n2 threads with this stack: worker-1, worker-2\n  at sun.misc.Unsafe.park\n  at java.lang.Thread.run\n"
+        ));
+        assert!(rendered.contains("\n1 thread with this stack: main\n"));
+    }
+}
@@ -0,0 +1,140 @@
+//! `--thread-lifecycle` — the `START THREAD` and `END THREAD` records tell
+//! which threads ran during the profiling and which were still alive when the
+//! dump was taken. An ended thread whose `java.lang.Thread` object is still in
+//! the heap is reported, something keeps a reference to it.
+
+use std::fmt::Write;
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::utils::{pluralize, pretty_timestamp_utc};
+
+#[derive(Serialize, JsonSchema, Debug, Clone)]
+pub struct ThreadLifecycle {
+    pub serial_number: u32,
+    pub object_id: u64,
+    pub name: String,
+    pub group: Option<String>,
+    // epoch milliseconds, `None` when the record or the file timestamp is missing
+    pub started_at: Option<u64>,
+    pub ended_at: Option<u64>,
+    // no `END THREAD` record for it
+    pub alive: bool,
+    // the `java.lang.Thread` object is in the dump
+    pub object_in_heap: bool,
+}
+
+impl ThreadLifecycle {
+    fn is_lingering(&self) -> bool {
+        !self.alive && self.object_in_heap
+    }
+}
+
+#[derive(Serialize, JsonSchema, Clone)]
+pub struct ThreadLifecycleReport {
+    pub threads: Vec<ThreadLifecycle>,
+}
+
+impl ThreadLifecycleReport {
+    pub fn new(mut threads: Vec<ThreadLifecycle>) -> Self {
+        threads.sort_unstable_by_key(|thread| thread.serial_number);
+        Self { threads }
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let alive = self.threads.iter().filter(|t| t.alive).count();
+        let _ = writeln!(
+            out,
+            "\nFound {}, {alive} alive at dump time and {} ended:\n",
+            pluralize(self.threads.len(), "thread", "threads"),
+            self.threads.len() - alive
+        );
+        for thread in &self.threads {
+            let group = thread
+                .group
+                .as_ref()
+                .map(|group| format!(" (group {group})"))
+                .unwrap_or_default();
+            let started = thread
+                .started_at
+                .map_or_else(|| "unknown".to_string(), pretty_timestamp_utc);
+            let ended = if thread.alive {
+                "alive".to_string()
+            } else {
+                let ended = thread
+                    .ended_at
+                    .map_or_else(|| "unknown".to_string(), pretty_timestamp_utc);
+                format!("ended {ended}")
+            };
+            let _ = writeln!(
+                out,
+                "  #{} {}{group}: started {started}, {ended}",
+                thread.serial_number, thread.name
+            );
+        }
+        let lingering: Vec<_> = self.threads.iter().filter(|t| t.is_lingering()).collect();
+        if !lingering.is_empty() {
+            let have = if lingering.len() == 1 {
+                "still has its"
+            } else {
+                "still have their"
+            };
+            let _ = writeln!(
+                out,
+                "\nWarning: {} {have} java.lang.Thread object in the heap:",
+                pluralize(lingering.len(), "ended thread", "ended threads")
+            );
+            for thread in lingering {
+                let _ = writeln!(
+                    out,
+                    "  #{} {} <0x{:x}>",
+                    thread.serial_number, thread.name, thread.object_id
+                );
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn thread(serial_number: u32, alive: bool, object_in_heap: bool) -> ThreadLifecycle {
+        ThreadLifecycle {
+            serial_number,
+            object_id: 0x100 + u64::from(serial_number),
+            name: format!("worker-{serial_number}"),
+            group: Some("main".to_string()),
+            started_at: Some(1_608_192_273_831),
+            ended_at: None,
+            alive,
+            object_in_heap,
+        }
+    }
+
+    #[test]
+    fn lists_threads_and_warns_about_ended_ones_still_in_heap() {
+        let report = ThreadLifecycleReport::new(vec![
+            thread(3, false, true),
+            thread(1, true, true),
+            thread(2, false, false),
+        ]);
+
+        let rendered = report.render();
+        assert!(rendered.starts_with(
+            "\nFound 3 threads, 1 alive at dump time and 2 ended:\n\n  #1 worker-1 (group main): started 2020-12-17 08:04:33 UTC, alive\n  #2 worker-2 (group main): started 2020-12-17 08:04:33 UTC, ended unknown\n"
+        ));
+        assert!(rendered.ends_with(
+            "\nWarning: 1 ended thread still has its java.lang.Thread object in the heap:\n  #3 worker-3 <0x103>\n"
+        ));
+    }
+
+    #[test]
+    fn no_warning_without_lingering_threads() {
+        let report = ThreadLifecycleReport::new(vec![thread(1, true, true)]);
+        assert!(!report.render().contains("Warning"));
+    }
+}
@@ -0,0 +1,340 @@
+//! `hprof-slurp trend <FILE>...` — per-class series of shallow bytes and
+//! instances across several dumps of the same process ordered by capture
+//! time. Classes growing at every capture are the strongest leak signal, they
+//! are ranked by growth per minute, or per the unit given with `--per` so slow
+//! leaks over days do not round to zero.
+
+use std::cmp::Reverse;
+use std::fmt::Write;
+
+use crate::diff::totals_by_class_name;
+use crate::rendered_result::ClassAllocationStats;
+use crate::table_export::TableFormat;
+use crate::utils::{pluralize, pretty_bytes_size, pretty_signed_bytes_rate, pretty_timestamp_utc};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeUnit {
+    name: &'static str,
+    // in the column headers
+    abbreviation: &'static str,
+    millis: u64,
+}
+
+// the units the rates can be expressed in, see `--per`
+const TIME_UNITS: [TimeUnit; 4] = [
+    TimeUnit {
+        name: "second",
+        abbreviation: "s",
+        millis: 1_000,
+    },
+    TimeUnit {
+        name: "minute",
+        abbreviation: "min",
+        millis: 60_000,
+    },
+    TimeUnit {
+        name: "hour",
+        abbreviation: "h",
+        millis: 3_600_000,
+    },
+    TimeUnit {
+        name: "day",
+        abbreviation: "day",
+        millis: 86_400_000,
+    },
+];
+
+// One of `second`, `minute`, `hour` or `day`.
+pub fn parse_time_unit(raw: &str) -> Result<TimeUnit, String> {
+    TIME_UNITS
+        .into_iter()
+        .find(|unit| unit.name == raw)
+        .ok_or_else(|| format!("unknown time unit `{raw}`, use second, minute, hour or day"))
+}
+
+pub struct TrendDump {
+    pub file: String,
+    pub captured_at_epoch_millis: u64,
+    pub total_bytes: u64,
+}
+
+pub struct ClassTrend {
+    pub class_name: String,
+    // one value per dump in capture order, `0` when the class is absent
+    pub instances: Vec<u64>,
+    pub bytes: Vec<u64>,
+}
+
+impl ClassTrend {
+    // Shallow size larger at each capture than at the previous one.
+    pub fn grows_monotonically(&self) -> bool {
+        self.bytes.windows(2).all(|pair| pair[1] > pair[0])
+    }
+
+    fn delta_bytes(&self) -> i64 {
+        self.bytes[self.bytes.len() - 1] as i64 - self.bytes[0] as i64
+    }
+
+    fn delta_instances(&self) -> i64 {
+        self.instances[self.instances.len() - 1] as i64 - self.instances[0] as i64
+    }
+}
+
+pub struct TrendReport {
+    pub dumps: Vec<TrendDump>,
+    // every class of any dump, by name
+    pub classes: Vec<ClassTrend>,
+}
+
+impl TrendReport {
+    // `(file, capture time, histogram)` per dump, in any order.
+    pub fn new(mut snapshots: Vec<(String, u64, Vec<ClassAllocationStats>)>) -> Self {
+        // stable, dumps captured at the same time keep their order
+        snapshots.sort_by_key(|(_, captured_at, _)| *captured_at);
+        let totals: Vec<_> = snapshots
+            .iter()
+            .map(|(_, _, memory_usage)| totals_by_class_name(memory_usage))
+            .collect();
+
+        let mut class_names: Vec<&str> = totals
+            .iter()
+            .flat_map(|by_name| by_name.keys().copied())
+            .collect();
+        class_names.sort_unstable();
+        class_names.dedup();
+
+        let classes = class_names
+            .into_iter()
+            .map(|class_name| {
+                let (instances, bytes) = totals
+                    .iter()
+                    .map(|by_name| by_name.get(class_name).copied().unwrap_or_default())
+                    .unzip();
+                ClassTrend {
+                    class_name: class_name.to_string(),
+                    instances,
+                    bytes,
+                }
+            })
+            .collect();
+
+        let dumps = snapshots
+            .iter()
+            .map(|(file, captured_at, memory_usage)| TrendDump {
+                file: file.clone(),
+                captured_at_epoch_millis: *captured_at,
+                total_bytes: memory_usage.iter().map(|s| s.allocation_size_bytes).sum(),
+            })
+            .collect();
+        Self { dumps, classes }
+    }
+
+    // Milliseconds between the first and the last capture.
+    fn elapsed_millis(&self) -> u64 {
+        let first = self.dumps.first().map_or(0, |d| d.captured_at_epoch_millis);
+        let last = self.dumps.last().map_or(0, |d| d.captured_at_epoch_millis);
+        last - first
+    }
+
+    // `None` when all the dumps were captured at the same time.
+    fn per_time_unit(&self, delta: i64, unit: TimeUnit) -> Option<f64> {
+        let units = self.elapsed_millis() as f64 / unit.millis as f64;
+        (units > 0.0).then(|| delta as f64 / units)
+    }
+
+    pub fn render(&self, top: usize, unit: TimeUnit) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "\nHeap trend of raw shallow sizes over {} spanning {:.1} {}s:",
+            pluralize(self.dumps.len(), "dump", "dumps"),
+            self.elapsed_millis() as f64 / unit.millis as f64,
+            unit.name
+        );
+        for (index, dump) in self.dumps.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "  {}. {} (captured at {}, {})",
+                index + 1,
+                dump.file,
+                pretty_timestamp_utc(dump.captured_at_epoch_millis),
+                pretty_bytes_size(dump.total_bytes)
+            );
+        }
+
+        let mut growing: Vec<_> = self
+            .classes
+            .iter()
+            .filter(|class| class.grows_monotonically())
+            .collect();
+        if growing.is_empty() {
+            let _ = writeln!(out, "\nNo class grows at every capture.");
+            return out;
+        }
+        growing.sort_by_key(|class| Reverse(class.delta_bytes()));
+
+        let shown = growing.len().min(top);
+        let _ = writeln!(
+            out,
+            "\nTop {shown} of {} growing at every capture (by growth per {}):\n",
+            pluralize(growing.len(), "class", "classes"),
+            unit.name
+        );
+        let _ = writeln!(
+            out,
+            "{:>14} {:>15}  Class name",
+            format!("Δ size/{}", unit.abbreviation),
+            format!("Δ instances/{}", unit.abbreviation)
+        );
+        for class in growing.into_iter().take(top) {
+            let (bytes_rate, instances_rate) = match (
+                self.per_time_unit(class.delta_bytes(), unit),
+                self.per_time_unit(class.delta_instances(), unit),
+            ) {
+                (Some(bytes), Some(instances)) => {
+                    (pretty_signed_bytes_rate(bytes), format!("{instances:+.1}"))
+                }
+                _ => ("n/a".to_string(), "n/a".to_string()),
+            };
+            let bytes_series: Vec<_> = class.bytes.iter().map(|b| pretty_bytes_size(*b)).collect();
+            let instances_series: Vec<_> = class.instances.iter().map(u64::to_string).collect();
+            let _ = writeln!(
+                out,
+                "{bytes_rate:>14} {instances_rate:>15}  {}",
+                class.class_name
+            );
+            let _ = writeln!(out, "{:>32}sizes: {}", "", bytes_series.join(" → "));
+            let _ = writeln!(out, "{:>32}instances: {}", "", instances_series.join(" → "));
+        }
+        out
+    }
+
+    // One row per class and dump, in capture order.
+    pub fn render_csv(&self) -> String {
+        let format = TableFormat::Csv;
+        let mut out = String::from(
+            "class_name,file,captured_at_epoch_millis,instance_count,allocation_size_bytes\n",
+        );
+        for class in &self.classes {
+            for (index, dump) in self.dumps.iter().enumerate() {
+                let _ = writeln!(
+                    out,
+                    "{},{},{},{},{}",
+                    format.field(&class.class_name),
+                    format.field(&dump.file),
+                    dump.captured_at_epoch_millis,
+                    class.instances[index],
+                    class.bytes[index]
+                );
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(class_name: &str, instances: u64, bytes: u64) -> ClassAllocationStats {
+        ClassAllocationStats::new(class_name.to_string(), instances, 0, bytes)
+    }
+
+    fn report() -> TrendReport {
+        // given out of order, two minutes apart
+        TrendReport::new(vec![
+            (
+                "c.hprof".to_string(),
+                240_000,
+                vec![stats("Leak", 30, 3072), stats("Flat", 1, 64)],
+            ),
+            (
+                "a.hprof".to_string(),
+                0,
+                vec![stats("Leak", 10, 1024), stats("Flat", 1, 64)],
+            ),
+            (
+                "b.hprof".to_string(),
+                120_000,
+                vec![
+                    stats("Leak", 15, 1024),
+                    stats("Leak", 5, 512),
+                    stats("Spike", 4, 4096),
+                ],
+            ),
+        ])
+    }
+
+    #[test]
+    fn dumps_are_ordered_by_capture_time() {
+        let report = report();
+
+        let files: Vec<_> = report.dumps.iter().map(|d| d.file.as_str()).collect();
+        assert_eq!(files, vec!["a.hprof", "b.hprof", "c.hprof"]);
+
+        let leak = &report.classes[1];
+        assert_eq!(leak.class_name, "Leak");
+        // duplicate class names are summed per dump
+        assert_eq!(leak.bytes, vec![1024, 1536, 3072]);
+        assert_eq!(leak.instances, vec![10, 20, 30]);
+        assert!(leak.grows_monotonically());
+        // absent from the second dump
+        assert!(!report.classes[0].grows_monotonically());
+        assert!(!report.classes[2].grows_monotonically());
+    }
+
+    #[test]
+    fn monotonic_growth_is_ranked_per_minute() {
+        let rendered = report().render(10, parse_time_unit("minute").unwrap());
+
+        assert!(rendered.contains("over 3 dumps spanning 4.0 minutes:"));
+        assert!(rendered.contains("Top 1 of 1 class growing at every capture"));
+        assert!(rendered.contains("  +512.00bytes            +5.0  Leak\n"));
+        assert!(rendered.contains("sizes: 1.00KiB → 1.50KiB → 3.00KiB\n"));
+        assert!(rendered.contains("instances: 10 → 20 → 30\n"));
+        assert!(!rendered.contains("Spike"));
+    }
+
+    #[test]
+    fn slow_growth_over_days_keeps_fractional_rates_per_day() {
+        let day = 86_400_000;
+        let rendered = TrendReport::new(vec![
+            ("a.hprof".to_string(), 0, vec![stats("Slow", 1, 64)]),
+            ("b.hprof".to_string(), 5 * day, vec![stats("Slow", 2, 65)]),
+            ("c.hprof".to_string(), 10 * day, vec![stats("Slow", 3, 67)]),
+        ])
+        .render(10, parse_time_unit("day").unwrap());
+
+        assert!(rendered.contains("spanning 10.0 days:"));
+        assert!(rendered.contains("(by growth per day):"));
+        assert!(rendered.contains("\n    Δ size/day Δ instances/day  Class name\n"));
+        assert!(rendered.contains("    +0.30bytes            +0.2  Slow\n"));
+    }
+
+    #[test]
+    fn csv_has_a_row_per_class_and_dump() {
+        let csv = report().render_csv();
+
+        assert_eq!(csv.lines().count(), 1 + 3 * 3);
+        assert!(csv.contains("\nSpike,a.hprof,0,0,0\nSpike,b.hprof,120000,4,4096\n"));
+    }
+
+    #[test]
+    fn dumps_of_the_same_time_have_no_rate() {
+        let rendered = TrendReport::new(vec![
+            ("a.hprof".to_string(), 60_000, vec![stats("Leak", 1, 64)]),
+            ("b.hprof".to_string(), 60_000, vec![stats("Leak", 2, 128)]),
+        ])
+        .render(10, parse_time_unit("minute").unwrap());
+
+        assert!(rendered.contains("over 2 dumps spanning 0.0 minutes:"));
+        assert!(rendered.contains("Top 1 of 1 class growing at every capture"));
+        assert!(rendered.contains("           n/a             n/a  Leak\n"));
+    }
+
+    #[test]
+    fn time_units_are_named() {
+        assert_eq!(parse_time_unit("hour").unwrap().millis, 3_600_000);
+        assert!(parse_time_unit("min").is_err());
+    }
+}
@@ -1,3 +1,5 @@
+use std::fmt::Display;
+
 const KILOBYTE: f64 = 1024.0;
 const MEGABYTE: f64 = KILOBYTE * KILOBYTE;
 const GIGABYTE: f64 = KILOBYTE * MEGABYTE;
@@ -33,6 +35,20 @@ fn civil_from_days(days: u64) -> (u64, u64, u64) {
     }
 }
 
+// `count` followed by the singular or the plural form of the noun, e.g.
+// `1 heap` and `2 heaps`.
+pub fn pluralize<T>(count: T, singular: &str, plural: &str) -> String
+where
+    T: Display + PartialEq + From<u8>,
+{
+    let noun = if count == T::from(1) {
+        singular
+    } else {
+        plural
+    };
+    format!("{count} {noun}")
+}
+
 // Like [`pretty_bytes_size`] but for deltas, with an explicit sign.
 pub fn pretty_signed_bytes_size(delta: i64) -> String {
     if delta < 0 {
@@ -42,8 +58,25 @@ pub fn pretty_signed_bytes_size(delta: i64) -> String {
     }
 }
 
+// Like [`pretty_signed_bytes_size`] for a fractional delta, e.g. a rate.
+pub fn pretty_signed_bytes_rate(delta: f64) -> String {
+    let sign = if delta < 0.0 { '-' } else { '+' };
+    format!("{sign}{}", pretty_size(delta.abs()))
+}
+
+// Escapes text for HTML and SVG content and attribute values.
+pub fn escape_markup(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
 pub fn pretty_bytes_size(len: u64) -> String {
-    let float_len = len as f64;
+    pretty_size(len as f64)
+}
+
+fn pretty_size(float_len: f64) -> String {
     let (unit, value) = if float_len >= GIGABYTE {
         ("GiB", float_len / GIGABYTE)
     } else if float_len >= MEGABYTE {
@@ -58,7 +91,10 @@ pub fn pretty_bytes_size(len: u64) -> String {
 
 #[cfg(test)]
 mod tests {
+    use super::escape_markup;
+    use super::pluralize;
     use super::pretty_bytes_size;
+    use super::pretty_signed_bytes_rate;
     use super::pretty_timestamp_utc;
 
     #[test]
@@ -120,4 +156,25 @@ mod tests {
         let size: u64 = 512;
         assert_eq!(pretty_bytes_size(size), "512.00bytes");
     }
+
+    #[test]
+    fn pretty_rate_keeps_fractions() {
+        assert_eq!(pretty_signed_bytes_rate(0.25), "+0.25bytes");
+        assert_eq!(pretty_signed_bytes_rate(-1536.0), "-1.50KiB");
+    }
+
+    #[test]
+    fn pluralize_by_count() {
+        assert_eq!(pluralize(0, "class", "classes"), "0 classes");
+        assert_eq!(pluralize(1, "class", "classes"), "1 class");
+        assert_eq!(pluralize(2, "heap", "heaps"), "2 heaps");
+    }
+
+    #[test]
+    fn escape_markup_special_characters() {
+        assert_eq!(
+            escape_markup(r#"Map<K, V> & "x""#),
+            "Map&lt;K, V&gt; &amp; &quot;x&quot;"
+        );
+    }
 }
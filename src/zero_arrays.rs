@@ -0,0 +1,289 @@
+//! `--zero-arrays` — primitive arrays whose body is entirely (or mostly) made
+//! of zero elements. Those are typically buffers allocated up front and never
+//! written, their elements are pure waste.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fmt::Write;
+
+use ahash::AHashMap;
+use schemars::JsonSchema;
+use serde::{Serialize, Serializer};
+
+use crate::parser::gc_record::FieldType;
+use crate::parser::gc_record::primitive_byte_size;
+use crate::result_recorder::primitive_array_label;
+use crate::utils::pretty_bytes_size;
+
+// Number of largest offenders reported with their object ids.
+const LARGEST_OFFENDERS: usize = 10;
+
+#[derive(Serialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ZeroArray {
+    pub size_bytes: u64,
+    pub object_id: u64,
+    pub number_of_elements: u32,
+    pub zero_elements: u32,
+    // written as its array type, e.g. `byte[]`
+    #[serde(rename = "array_type", serialize_with = "serialize_array_type")]
+    #[schemars(with = "String")]
+    pub element_type: FieldType,
+}
+
+fn serialize_array_type<S: Serializer>(
+    element_type: &FieldType,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&primitive_array_label(*element_type))
+}
+
+impl ZeroArray {
+    const fn is_all_zero(&self) -> bool {
+        self.zero_elements == self.number_of_elements
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ZeroArrayCounter {
+    pub arrays: u64,
+    pub wasted_bytes: u64,
+}
+
+// Zero-filled arrays of one array type, e.g. `byte[]`.
+#[derive(Serialize, JsonSchema, Debug, Clone)]
+pub struct ZeroArrayTypeStats {
+    pub array_type: String,
+    pub arrays: u64,
+    pub wasted_bytes: u64,
+}
+
+impl ZeroArrayCounter {
+    const fn add_array(&mut self, wasted_bytes: u64) {
+        self.arrays += 1;
+        self.wasted_bytes += wasted_bytes;
+    }
+}
+
+pub struct ZeroArrayRecorder {
+    // Arrays with at least this percentage of zero elements are reported as
+    // mostly zero, only all-zero arrays are reported when absent.
+    mostly_zero_percent: Option<u8>,
+    all_zero_by_type: AHashMap<FieldType, ZeroArrayCounter>,
+    mostly_zero_by_type: AHashMap<FieldType, ZeroArrayCounter>,
+    // min-heap holding the largest offenders seen so far
+    largest: BinaryHeap<Reverse<ZeroArray>>,
+}
+
+impl ZeroArrayRecorder {
+    pub fn new(mostly_zero_percent: Option<u8>) -> Self {
+        Self {
+            mostly_zero_percent,
+            all_zero_by_type: AHashMap::new(),
+            mostly_zero_by_type: AHashMap::new(),
+            largest: BinaryHeap::with_capacity(LARGEST_OFFENDERS + 1),
+        }
+    }
+
+    pub fn add_array(&mut self, array: ZeroArray) {
+        // empty arrays are trivially zero-filled but waste nothing
+        if array.number_of_elements == 0 || array.zero_elements == 0 {
+            return;
+        }
+        let wasted_bytes = u64::from(array.zero_elements) * primitive_byte_size(array.element_type);
+        if array.is_all_zero() {
+            self.all_zero_by_type
+                .entry(array.element_type)
+                .or_default()
+                .add_array(wasted_bytes);
+        } else if let Some(percent) = self.mostly_zero_percent {
+            let zero_percent =
+                u64::from(array.zero_elements) * 100 / u64::from(array.number_of_elements);
+            if zero_percent < u64::from(percent) {
+                return;
+            }
+            self.mostly_zero_by_type
+                .entry(array.element_type)
+                .or_default()
+                .add_array(wasted_bytes);
+        } else {
+            return;
+        }
+        self.largest.push(Reverse(array));
+        if self.largest.len() > LARGEST_OFFENDERS {
+            self.largest.pop();
+        }
+    }
+
+    pub fn report(self) -> ZeroArrayReport {
+        let sorted_by_waste = |by_type: AHashMap<FieldType, ZeroArrayCounter>| {
+            let mut counters: Vec<_> = by_type
+                .into_iter()
+                .map(|(field_type, counter)| ZeroArrayTypeStats {
+                    array_type: primitive_array_label(field_type),
+                    arrays: counter.arrays,
+                    wasted_bytes: counter.wasted_bytes,
+                })
+                .collect();
+            counters.sort_by(|a, b| {
+                b.wasted_bytes
+                    .cmp(&a.wasted_bytes)
+                    .then_with(|| a.array_type.cmp(&b.array_type))
+            });
+            counters
+        };
+        let mut largest: Vec<ZeroArray> = self.largest.into_iter().map(|r| r.0).collect();
+        largest.sort_unstable_by(|a, b| b.cmp(a));
+        ZeroArrayReport {
+            mostly_zero_percent: self.mostly_zero_percent,
+            all_zero: sorted_by_waste(self.all_zero_by_type),
+            mostly_zero: sorted_by_waste(self.mostly_zero_by_type),
+            largest,
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema, Clone)]
+pub struct ZeroArrayReport {
+    pub mostly_zero_percent: Option<u8>,
+    pub all_zero: Vec<ZeroArrayTypeStats>,
+    pub mostly_zero: Vec<ZeroArrayTypeStats>,
+    pub largest: Vec<ZeroArray>,
+}
+
+impl ZeroArrayReport {
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "\nZero-filled primitive arrays:\n");
+        Self::render_counters(&mut out, &self.all_zero);
+        if let Some(percent) = self.mostly_zero_percent {
+            let _ = writeln!(
+                out,
+                "\nMostly-zero primitive arrays (at least {percent}% zero elements):\n"
+            );
+            Self::render_counters(&mut out, &self.mostly_zero);
+        }
+        if !self.largest.is_empty() {
+            let _ = writeln!(out, "\nLargest offenders:\n");
+            let _ = writeln!(
+                out,
+                "{:>12} {:>8} {:>12}  {:<20}  Object id",
+                "Size", "Zero %", "Elements", "Type"
+            );
+            for array in &self.largest {
+                let zero_percent =
+                    u64::from(array.zero_elements) * 100 / u64::from(array.number_of_elements);
+                let _ = writeln!(
+                    out,
+                    "{:>12} {:>8} {:>12}  {:<20}  0x{:x}",
+                    pretty_bytes_size(array.size_bytes),
+                    format!("{zero_percent}%"),
+                    array.number_of_elements,
+                    primitive_array_label(array.element_type),
+                    array.object_id
+                );
+            }
+        }
+        out
+    }
+
+    fn render_counters(out: &mut String, counters: &[ZeroArrayTypeStats]) {
+        if counters.is_empty() {
+            let _ = writeln!(out, "  none found");
+            return;
+        }
+        let _ = writeln!(out, "{:>12} {:>10}  Type", "Wasted", "Arrays");
+        for counter in counters {
+            let _ = writeln!(
+                out,
+                "{:>12} {:>10}  {}",
+                pretty_bytes_size(counter.wasted_bytes),
+                counter.arrays,
+                counter.array_type
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn array(
+        object_id: u64,
+        element_type: FieldType,
+        number_of_elements: u32,
+        zero_elements: u32,
+    ) -> ZeroArray {
+        ZeroArray {
+            size_bytes: 16 + u64::from(number_of_elements) * primitive_byte_size(element_type),
+            object_id,
+            number_of_elements,
+            zero_elements,
+            element_type,
+        }
+    }
+
+    #[test]
+    fn all_zero_arrays_are_counted_per_element_type() {
+        let mut recorder = ZeroArrayRecorder::new(None);
+        recorder.add_array(array(1, FieldType::Byte, 100, 100));
+        recorder.add_array(array(2, FieldType::Byte, 50, 50));
+        recorder.add_array(array(3, FieldType::Int, 10, 10));
+        // partially written arrays are ignored without a threshold
+        recorder.add_array(array(4, FieldType::Int, 10, 9));
+        // empty arrays waste nothing
+        recorder.add_array(array(5, FieldType::Long, 0, 0));
+
+        let report = recorder.report();
+
+        assert_eq!(report.all_zero.len(), 2);
+        let bytes = &report.all_zero[0];
+        assert_eq!(bytes.array_type, "byte[]");
+        assert_eq!(bytes.arrays, 2);
+        assert_eq!(bytes.wasted_bytes, 150);
+        let ints = &report.all_zero[1];
+        assert_eq!(ints.array_type, "int[]");
+        assert_eq!(ints.wasted_bytes, 40);
+        assert!(report.mostly_zero.is_empty());
+        assert_eq!(report.largest.len(), 3);
+        assert_eq!(report.largest[0].object_id, 1);
+    }
+
+    #[test]
+    fn mostly_zero_arrays_respect_threshold() {
+        let mut recorder = ZeroArrayRecorder::new(Some(90));
+        recorder.add_array(array(1, FieldType::Char, 100, 95));
+        recorder.add_array(array(2, FieldType::Char, 100, 89));
+
+        let report = recorder.report();
+
+        assert!(report.all_zero.is_empty());
+        assert_eq!(report.mostly_zero.len(), 1);
+        assert_eq!(report.mostly_zero[0].arrays, 1);
+        assert_eq!(report.mostly_zero[0].wasted_bytes, 190);
+        let rendered = report.render();
+        assert!(rendered.contains("at least 90% zero elements"));
+        assert!(rendered.contains("95%"));
+        assert!(rendered.contains("0x1"));
+    }
+
+    #[test]
+    fn largest_offenders_are_capped() {
+        let mut recorder = ZeroArrayRecorder::new(None);
+        for i in 1..=20 {
+            recorder.add_array(array(i, FieldType::Byte, u32::try_from(i).unwrap(), 1));
+        }
+        // only the array of one element is entirely zero
+        assert_eq!(recorder.report().largest.len(), 1);
+
+        let mut recorder = ZeroArrayRecorder::new(None);
+        for i in 1..=20 {
+            let len = u32::try_from(i).unwrap() * 8;
+            recorder.add_array(array(i, FieldType::Byte, len, len));
+        }
+        let report = recorder.report();
+        assert_eq!(report.largest.len(), LARGEST_OFFENDERS);
+        assert_eq!(report.largest[0].object_id, 20);
+        assert_eq!(report.largest[LARGEST_OFFENDERS - 1].object_id, 11);
+    }
+}